@@ -0,0 +1,162 @@
+use std::{
+    fmt::{ Display, Formatter, Result as FmtResult },
+    sync::{ Arc, Weak, RwLock },
+    collections::BTreeSet,
+    marker::PhantomData
+};
+
+use bionet_common::{
+    sensor::SensorData,
+    data::{ DataDeductor, DataTypeValue }
+};
+
+// The elements on either side of a key, as left behind by unlink.
+pub(crate) type Neighbours<Key, const ORDER: usize> = (
+    Option<Arc<RwLock<Element<Key, ORDER>>>>, Option<Arc<RwLock<Element<Key, ORDER>>>>
+);
+
+/// Everything a reader could use to break the tree order or the node aggregates
+/// is crate-private; write access to an element lock only reaches `parent`.
+pub struct Element<Key, const ORDER: usize>
+where Key: SensorData, [(); ORDER + 1]: {
+    pub(crate) key: Key,
+    pub(crate) counter: usize,
+    pub(crate) weighted_counter: f32,
+    pub(crate) records: Option<BTreeSet<usize>>,
+    pub parent: Arc<str>,
+    pub(crate) self_ptr: Weak<RwLock<Element<Key, ORDER>>>,
+    pub(crate) next: Option<(Weak<RwLock<Element<Key, ORDER>>>, f32)>,
+    pub(crate) prev: Option<(Weak<RwLock<Element<Key, ORDER>>>, f32)>,
+    pub(crate) data_type: PhantomData<Key>
+}
+
+impl<Key, const ORDER: usize> Element<Key, ORDER>
+where
+    Key: SensorData, [(); ORDER + 1]:,
+    PhantomData<Key>: DataDeductor,
+    DataTypeValue: From<Key>
+{
+    pub fn new(key: &Key, parent: &Arc<str>)
+    -> Arc<RwLock<Element<Key, ORDER>>> {
+        let element_ptr = Arc::new(
+            RwLock::new(
+                Element {
+                    key: *dyn_clone::clone_box(key),
+                    counter: 1,
+                    weighted_counter: 1.0f32,
+                    records: None,
+                    parent: parent.clone(),
+                    self_ptr: Weak::new(),
+                    next: None,
+                    prev: None,
+                    data_type: PhantomData
+                }
+            )
+        );
+
+        element_ptr.write().unwrap().self_ptr = Arc::downgrade(&element_ptr);
+        element_ptr
+    }
+
+    pub fn key(&self) -> &Key { &self.key }
+
+    pub fn counter(&self) -> usize { self.counter }
+
+    pub fn weighted_counter(&self) -> f32 { self.weighted_counter }
+
+    pub fn records(&self) -> Option<&BTreeSet<usize>> { self.records.as_ref() }
+
+    /// Next element in key order and the weight of the link to it.
+    pub fn next(&self) -> Option<(Arc<RwLock<Element<Key, ORDER>>>, f32)> {
+        self.next.as_ref().and_then(|(next, weight)| Some((next.upgrade()?, *weight)))
+    }
+
+    /// Previous element in key order and the weight of the link to it.
+    pub fn prev(&self) -> Option<(Arc<RwLock<Element<Key, ORDER>>>, f32)> {
+        self.prev.as_ref().and_then(|(prev, weight)| Some((prev.upgrade()?, *weight)))
+    }
+
+    // Counter and weight of the occurrences inserted without a record id;
+    // every recorded occurrence counts once with weight 1.
+    pub(crate) fn anonymous_counters(&self) -> (usize, f32) {
+        let recorded = self.records.as_ref().map_or(0, |records| records.len());
+        (self.counter - recorded, self.weighted_counter - recorded as f32)
+    }
+
+    // Adds another element's occurrences; a record held by both is counted once.
+    pub(crate) fn absorb(&mut self, counter: usize, weighted_counter: f32, records: Option<BTreeSet<usize>>) {
+        self.counter += counter;
+        self.weighted_counter += weighted_counter;
+        if let Some(records) = records {
+            let own_records = self.records.get_or_insert_with(BTreeSet::new);
+            let repeated = own_records.intersection(&records).count();
+            own_records.extend(records);
+            self.counter -= repeated;
+            self.weighted_counter -= repeated as f32;
+        }
+    }
+
+    pub(crate) fn set_connections(
+        element_ptr: &Arc<RwLock<Element<Key, ORDER>>>,
+        prev_opt: Option<&Arc<RwLock<Element<Key, ORDER>>>>,
+        next_opt: Option<&Arc<RwLock<Element<Key, ORDER>>>>,
+        range: f32
+    ) {
+        let mut element = element_ptr.write().unwrap();
+
+        if let Some(prev_ptr) = prev_opt {
+            let weight = element.weight(&*prev_ptr.read().unwrap(), range);
+            element.prev = Some((Arc::downgrade(prev_ptr), weight));
+            prev_ptr.write().unwrap().next = Some((Arc::downgrade(element_ptr), weight));
+        } else {
+            element.prev = None;
+        }
+
+        if let Some(next_ptr) = next_opt {
+            let weight = element.weight(&*next_ptr.read().unwrap(), range);
+            element.next = Some((Arc::downgrade(next_ptr), weight));
+            next_ptr.write().unwrap().prev = Some((Arc::downgrade(element_ptr), weight));
+        } else {
+            element.next = None;
+        }
+    }
+
+    pub(crate) fn link(
+        prev_ptr: &Arc<RwLock<Element<Key, ORDER>>>,
+        next_ptr: &Arc<RwLock<Element<Key, ORDER>>>,
+        range: f32
+    ) {
+        let weight = prev_ptr.read().unwrap().weight(&*next_ptr.read().unwrap(), range);
+        prev_ptr.write().unwrap().next = Some((Arc::downgrade(next_ptr), weight));
+        next_ptr.write().unwrap().prev = Some((Arc::downgrade(prev_ptr), weight));
+    }
+
+    pub(crate) fn unlink(element_ptr: &Arc<RwLock<Element<Key, ORDER>>>, range: f32) -> Neighbours<Key, ORDER> {
+        let (prev_opt, next_opt) = {
+            let mut element = element_ptr.write().unwrap();
+            let prev_opt = element.prev.take().and_then(|prev| prev.0.upgrade());
+            let next_opt = element.next.take().and_then(|next| next.0.upgrade());
+            (prev_opt, next_opt)
+        };
+
+        match (&prev_opt, &next_opt) {
+            (Some(prev_ptr), Some(next_ptr)) => Self::link(prev_ptr, next_ptr, range),
+            (Some(prev_ptr), None) => prev_ptr.write().unwrap().next = None,
+            (None, Some(next_ptr)) => next_ptr.write().unwrap().prev = None,
+            (None, None) => {}
+        }
+
+        (prev_opt, next_opt)
+    }
+
+    pub fn weight(&self, other: &Self, range: f32) -> f32 {
+        1.0f32 - (other.key.distance(&self.key) as f32).abs() / range
+    }
+}
+
+impl<Key, const ORDER: usize> Display for Element<Key, ORDER>
+where Key: SensorData, [(); ORDER + 1]: {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "[{}:{}]", self.key, self.counter)
+    }
+}
\ No newline at end of file
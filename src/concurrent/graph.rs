@@ -0,0 +1,1339 @@
+use std::{
+    sync::{ Arc, RwLock },
+    cmp::Ordering::*,
+    collections::BTreeSet,
+    marker::PhantomData,
+    ops::{ RangeBounds, Bound::{ self, * } }
+};
+
+use bionet_common::{
+    sensor::SensorData,
+    data::{ DataCategory, DataType, DataDeductor, DataTypeValue }
+};
+
+use crate::{
+    simple::graph::subtract_counters,
+    neural::{
+        self,
+        graph::{ ASAGraphStats, ASAGraphHistogram, ElementCounts, key_to_f64 }
+    }
+};
+
+use super::{
+    element::Element,
+    node::Node
+};
+
+pub use crate::simple::graph::CounterSemantics;
+
+#[derive(Clone)]
+pub struct ASAGraph<Key, const ORDER: usize = 25>
+where Key: SensorData, [(); ORDER + 1]: {
+    pub name: Arc<str>,
+    pub(crate) root: Arc<RwLock<Node<Key, ORDER>>>,
+    pub element_min: Option<Arc<RwLock<Element<Key, ORDER>>>>,
+    pub element_max: Option<Arc<RwLock<Element<Key, ORDER>>>>,
+    pub key_min: Option<Key>,
+    pub key_max: Option<Key>,
+    pub(crate) data_type: PhantomData<Key>
+}
+
+impl<Key, const ORDER: usize> ASAGraph<Key, ORDER>
+where
+    Key: SensorData,
+    [(); ORDER + 1]:,
+    PhantomData<Key>: DataDeductor,
+    DataTypeValue: From<Key>
+{
+    pub fn new(name: &str) -> ASAGraph<Key, ORDER> {
+        if ORDER < 3 {
+            panic!("Graph can't be created, ORDER must be greater than 2");
+        }
+
+        ASAGraph {
+            name: Arc::from(name),
+            root: Arc::new(RwLock::new(Node::<Key, ORDER>::new(true, None))),
+            element_min: None,
+            element_max: None,
+            key_min: None,
+            key_max: None,
+            data_type: PhantomData
+        }
+    }
+
+    pub fn new_arc(name: &str) -> Arc<RwLock<ASAGraph<Key, ORDER>>> {
+        Arc::new(RwLock::new(ASAGraph::new(name)))
+    }
+
+    pub fn new_from_vec(name: &str, data: &[Key]) -> Self {
+        let mut graph = Self::new(name);
+        for point in data { graph.insert(point); }
+        graph
+    }
+
+    /// Sorts the keys and counts duplicates into single elements before building the tree bottom-up.
+    /// Keys that are not equal to themselves, such as `f64::NAN`, have no place in the order and are skipped.
+    pub fn new_bulk(name: &str, data: &[Key]) -> Self {
+        let mut graph = Self::new(name);
+        let mut keys: Vec<Key> = data.iter()
+            .filter(|key| key.partial_compare(*key) == Some(Equal))
+            .map(|key| *dyn_clone::clone_box(key))
+            .collect();
+        keys.sort_by(|a, b| a.partial_compare(b).unwrap_or(Equal));
+
+        let mut elements: Vec<Arc<RwLock<Element<Key, ORDER>>>> = Vec::new();
+        for key in &keys {
+            let is_duplicate = match elements.last() {
+                Some(last) => last.read().unwrap().key.equals(key),
+                None => false
+            };
+            if is_duplicate {
+                let mut last = elements.last().unwrap().write().unwrap();
+                last.counter += 1;
+                last.weighted_counter += 1.0f32;
+            } else {
+                elements.push(Element::new(key, &graph.name));
+            }
+        }
+        graph.rebuild(elements);
+        graph
+    }
+
+    /// Like `new_bulk`, recording each record id once per key. Keys that are not equal to themselves are skipped.
+    pub fn new_bulk_records(name: &str, data: &[(Key, usize)]) -> Self {
+        let mut graph = Self::new(name);
+        let mut pairs: Vec<(Key, usize)> = data.iter()
+            .filter(|(key, _)| key.partial_compare(key) == Some(Equal))
+            .map(|(key, record)| (*dyn_clone::clone_box(key), *record))
+            .collect();
+        pairs.sort_by(|a, b| a.0.partial_compare(&b.0).unwrap_or(Equal).then(a.1.cmp(&b.1)));
+        pairs.dedup_by(|a, b| a.0.equals(&b.0) && a.1 == b.1);
+
+        let mut elements: Vec<Arc<RwLock<Element<Key, ORDER>>>> = Vec::new();
+        for (key, record) in &pairs {
+            let is_duplicate = match elements.last() {
+                Some(last) => last.read().unwrap().key.equals(key),
+                None => false
+            };
+            if is_duplicate {
+                let mut last = elements.last().unwrap().write().unwrap();
+                last.counter += 1;
+                last.weighted_counter += 1.0f32;
+                last.records.as_mut().unwrap().insert(*record);
+            } else {
+                let element = Element::new(key, &graph.name);
+                element.write().unwrap().records = Some(BTreeSet::from([*record]));
+                elements.push(element);
+            }
+        }
+        graph.rebuild(elements);
+        graph
+    }
+
+    pub fn id(&self) -> Arc<str> { self.name.clone() }
+
+    pub fn data_type(&self) -> DataType { self.data_type.data_type() }
+
+    pub fn data_category(&self) -> DataCategory { self.data_type.data_category() }
+
+    pub fn search(&self, key: &Key) -> Option<Arc<RwLock<Element<Key, ORDER>>>> {
+        let (node, index) = Node::find_node(&self.root, key)?;
+        let element = node.read().unwrap().elements[index].as_ref().unwrap().clone();
+        Some(element)
+    }
+
+    pub fn floor(&self, key: &Key) -> Option<Arc<RwLock<Element<Key, ORDER>>>> {
+        self.upper_bound_element(Included(key))
+    }
+
+    pub fn ceiling(&self, key: &Key) -> Option<Arc<RwLock<Element<Key, ORDER>>>> {
+        self.lower_bound_element(Included(key))
+    }
+
+    pub fn predecessor(&self, key: &Key) -> Option<Arc<RwLock<Element<Key, ORDER>>>> {
+        self.upper_bound_element(Excluded(key))
+    }
+
+    pub fn successor(&self, key: &Key) -> Option<Arc<RwLock<Element<Key, ORDER>>>> {
+        self.lower_bound_element(Excluded(key))
+    }
+
+    pub fn nearest(&self, key: &Key, k: usize) -> Vec<Arc<RwLock<Element<Key, ORDER>>>> {
+        self.expand_by_distance(key, |count, _| count < k)
+    }
+
+    pub fn within(&self, key: &Key, radius: f64) -> Vec<Arc<RwLock<Element<Key, ORDER>>>> {
+        self.expand_by_distance(key, |_, distance| distance <= radius)
+    }
+
+    fn expand_by_distance<F>(&self, key: &Key, mut is_accepted: F) -> Vec<Arc<RwLock<Element<Key, ORDER>>>>
+    where F: FnMut(usize, f64) -> bool {
+        let mut result = Vec::new();
+        let mut left = self.floor(key);
+        let mut right = self.successor(key);
+        loop {
+            let left_distance = left.as_ref().map(|element| key.distance(&element.read().unwrap().key).abs());
+            let right_distance = right.as_ref().map(|element| key.distance(&element.read().unwrap().key).abs());
+            let (is_left, distance) = match (left_distance, right_distance) {
+                (Some(left_distance), Some(right_distance)) if left_distance <= right_distance => (true, left_distance),
+                (Some(_), Some(right_distance)) => (false, right_distance),
+                (Some(left_distance), None) => (true, left_distance),
+                (None, Some(right_distance)) => (false, right_distance),
+                (None, None) => return result
+            };
+            if !is_accepted(result.len(), distance) { return result }
+
+            if is_left {
+                let element = left.take().unwrap();
+                left = element.read().unwrap().prev.as_ref().and_then(|prev| prev.0.upgrade());
+                result.push(element);
+            } else {
+                let element = right.take().unwrap();
+                right = element.read().unwrap().next.as_ref().and_then(|next| next.0.upgrade());
+                result.push(element);
+            }
+        }
+    }
+
+    pub fn iter(&self) -> ASAGraphIntoIterator<'_, Key, ORDER> { self.into_iter() }
+
+    pub fn range<R: RangeBounds<Key>>(&self, range: R) -> ASAGraphIntoIterator<'_, Key, ORDER> {
+        let front = self.lower_bound_element(range.start_bound());
+        let back = self.upper_bound_element(range.end_bound());
+        let is_empty = match (&front, &back) {
+            (Some(front), Some(back)) => {
+                front.read().unwrap().key.partial_compare(&back.read().unwrap().key) == Some(Greater)
+            },
+            _ => true
+        };
+
+        ASAGraphIntoIterator {
+            graph: self,
+            front: if is_empty { None } else { front },
+            back: if is_empty { None } else { back }
+        }
+    }
+
+    fn lower_bound_element(&self, bound: Bound<&Key>) -> Option<Arc<RwLock<Element<Key, ORDER>>>> {
+        let (key, include_key) = match bound {
+            Included(key) => (key, true),
+            Excluded(key) => (key, false),
+            Unbounded => return self.element_min.clone()
+        };
+
+        let mut result = None;
+        let mut node = self.root.clone();
+        loop {
+            let child = {
+                let node_ref = node.read().unwrap();
+                let mut index = 0;
+                while index < node_ref.size {
+                    match node_ref.keys[index].as_ref().unwrap().partial_compare(key) {
+                        Some(Less) => index += 1,
+                        Some(Equal) if !include_key => index += 1,
+                        _ => break
+                    }
+                }
+                if index < node_ref.size {
+                    result = Some(node_ref.elements[index].as_ref().unwrap().clone());
+                }
+                if node_ref.is_leaf { return result }
+                node_ref.children[index].as_ref().unwrap().clone()
+            };
+            node = child;
+        }
+    }
+
+    fn upper_bound_element(&self, bound: Bound<&Key>) -> Option<Arc<RwLock<Element<Key, ORDER>>>> {
+        let (key, include_key) = match bound {
+            Included(key) => (key, true),
+            Excluded(key) => (key, false),
+            Unbounded => return self.element_max.clone()
+        };
+
+        let mut result = None;
+        let mut node = self.root.clone();
+        loop {
+            let child = {
+                let node_ref = node.read().unwrap();
+                let mut index = 0;
+                while index < node_ref.size {
+                    match node_ref.keys[index].as_ref().unwrap().partial_compare(key) {
+                        Some(Less) => index += 1,
+                        Some(Equal) if include_key => index += 1,
+                        _ => break
+                    }
+                }
+                if index > 0 {
+                    result = Some(node_ref.elements[index - 1].as_ref().unwrap().clone());
+                }
+                if node_ref.is_leaf { return result }
+                node_ref.children[index].as_ref().unwrap().clone()
+            };
+            node = child;
+        }
+    }
+
+    pub fn insert(&mut self, key: &Key) -> Arc<RwLock<Element<Key, ORDER>>> {
+        let mut node = self.root.clone();
+
+        if node.read().unwrap().size == 0 { return self.insert_first_element(&node, key) }
+
+        if node.read().unwrap().size == Node::<Key, ORDER>::MAX_KEYS { node = self.split_root(); }
+
+        let (key_min, key_max) = self.extreme_keys().unwrap_or_else(|| {
+            panic!("element_min / element_min must not be nullptr")
+        });
+        let left_search = key.distance(key_max) > key.distance(key_min);
+
+        loop {
+            let node_insert_result = node.read().unwrap().insert_existing_key(key, left_search);
+            if let Some(el) = node_insert_result.0 {
+                Node::update_counts_upwards(&node);
+                return el
+            }
+            let mut index = node_insert_result.1;
+
+            if node.read().unwrap().is_leaf {
+                let element = Node::insert_key_leaf(&node, key, &self.name, self.key_range());
+                self.set_extrema(&element);
+                return element
+            } else {
+                let child_size = node.read().unwrap().children[index].as_ref().unwrap().read().unwrap().size;
+                if child_size == Node::<Key, ORDER>::MAX_KEYS {
+                    Node::split_child(&node, index);
+                    let ordering = key.partial_compare(node.read().unwrap().keys[index].as_ref().unwrap());
+                    if ordering == Some(Greater) {
+                        index += 1
+                    } else if ordering == Some(Equal) {
+                        continue
+                    }
+                }
+                let new_node = node.read().unwrap().children[index].as_ref().unwrap().clone();
+                node = new_node;
+            }
+        }
+    }
+
+    /// Returns `None` when `n` is 0 and `key` is not in the graph.
+    pub fn insert_n(&mut self, key: &Key, n: usize) -> Option<Arc<RwLock<Element<Key, ORDER>>>> {
+        self.insert_counted(key, n, n as f32)
+    }
+
+    pub fn insert_weighted(&mut self, key: &Key, weight: f32) -> Arc<RwLock<Element<Key, ORDER>>> {
+        self.insert_counted(key, 1, weight).unwrap()
+    }
+
+    fn insert_counted(
+        &mut self, key: &Key, counter: usize, weighted_counter: f32
+    ) -> Option<Arc<RwLock<Element<Key, ORDER>>>> {
+        if counter == 0 { return self.search(key) }
+        if self.search(key).is_some() {
+            return self.update_element(key, |element| {
+                element.counter += counter;
+                element.weighted_counter += weighted_counter;
+            })
+        }
+        self.insert(key);
+        self.update_element(key, |element| {
+            element.counter = counter;
+            element.weighted_counter = weighted_counter;
+        })
+    }
+
+    /// Each record adds one occurrence with weight 1, on top of the occurrences inserted
+    /// without a record id. Inserting a record the key already holds changes nothing.
+    pub fn insert_record(&mut self, key: &Key, record: usize) -> Arc<RwLock<Element<Key, ORDER>>> {
+        if let Some(element) = self.search(key) {
+            let is_recorded = element.read().unwrap().records.as_ref()
+                .is_some_and(|records| records.contains(&record));
+            if is_recorded { return element }
+        }
+        let element = self.insert(key);
+        element.write().unwrap().records.get_or_insert_with(BTreeSet::new).insert(record);
+        element
+    }
+
+    /// Takes away the one occurrence the record contributed, with weight 1.
+    pub fn remove_record(&mut self, key: &Key, record: usize) -> bool {
+        let is_removed = match self.search(key) {
+            Some(element) => {
+                element.write().unwrap().records.as_mut().is_some_and(|records| records.remove(&record))
+            },
+            None => false
+        };
+        if is_removed { self.remove_occurrence(key, 1.0f32); }
+        is_removed
+    }
+
+    pub fn records(&self, key: &Key) -> BTreeSet<usize> {
+        match self.search(key) {
+            Some(element) => element.read().unwrap().records.clone().unwrap_or_default(),
+            None => BTreeSet::new()
+        }
+    }
+
+    pub fn records_in_range<R: RangeBounds<Key>>(&self, range: R) -> BTreeSet<usize> {
+        let mut records = BTreeSet::new();
+        for element in self.range(range) {
+            records.extend(element.read().unwrap().records.iter().flatten());
+        }
+        records
+    }
+
+    /// Takes away one occurrence inserted without a record id and returns the remaining counter.
+    /// Returns `None` when the key is missing or all its occurrences are recorded;
+    /// those go through `remove_record`.
+    pub fn remove(&mut self, key: &Key) -> Option<usize> {
+        let (counter, weighted_counter) = self.search(key)?.read().unwrap().anonymous_counters();
+        if counter == 0 { return None }
+        self.remove_occurrence(key, weighted_counter / counter as f32)
+    }
+
+    /// Same as `remove`, subtracting `weight` from the weighted counter.
+    pub fn remove_weighted(&mut self, key: &Key, weight: f32) -> Option<usize> {
+        if self.search(key)?.read().unwrap().anonymous_counters().0 == 0 { return None }
+        self.remove_occurrence(key, weight)
+    }
+
+    fn remove_occurrence(&mut self, key: &Key, weight: f32) -> Option<usize> {
+        let counter = self.search(key)?.read().unwrap().counter;
+        if counter > 1 {
+            self.update_element(key, |element| {
+                element.counter -= 1;
+                element.weighted_counter -= weight;
+            });
+            Some(counter - 1)
+        } else {
+            self.remove_all(key);
+            Some(0)
+        }
+    }
+
+    pub fn remove_all(&mut self, key: &Key) -> Option<Arc<RwLock<Element<Key, ORDER>>>> {
+        let element = self.search(key)?;
+
+        Node::remove_key(&self.root, key);
+        self.shrink_root();
+
+        let (prev_opt, next_opt) = Element::unlink(&element, self.key_range());
+        self.unset_extrema(&element, prev_opt.as_ref(), next_opt.as_ref());
+
+        Some(element)
+    }
+
+    // Changes the element of key in place and refreshes the aggregates above it.
+    fn update_element<F>(&mut self, key: &Key, update: F) -> Option<Arc<RwLock<Element<Key, ORDER>>>>
+    where F: FnOnce(&mut Element<Key, ORDER>) {
+        let (node, index) = Node::find_node(&self.root, key)?;
+        let element = node.read().unwrap().elements[index].as_ref().unwrap().clone();
+        update(&mut element.write().unwrap());
+        Node::update_counts_upwards(&node);
+        Some(element)
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        // other may share elements with self through the derived Clone,
+        // so its counters are copied out before any element is locked for writing
+        let other_elements: Vec<ElementCounts<Key>> = other.iter()
+            .map(|element| {
+                let element = element.read().unwrap();
+                (
+                    *dyn_clone::clone_box(&element.key),
+                    element.counter,
+                    element.weighted_counter,
+                    element.records.clone()
+                )
+            })
+            .collect();
+        let mut elements = Vec::with_capacity(self.count_elements_unique() + other_elements.len());
+        {
+            let mut self_iter = self.iter().peekable();
+            let mut other_iter = other_elements.into_iter().peekable();
+            loop {
+                let ordering = match (self_iter.peek(), other_iter.peek()) {
+                    (Some(element), Some(other_element)) => {
+                        element.read().unwrap().key.partial_compare(&other_element.0)
+                    },
+                    (Some(_), None) => Some(Less),
+                    (None, Some(_)) => Some(Greater),
+                    (None, None) => break
+                };
+                match ordering {
+                    Some(Less) => elements.push(self_iter.next().unwrap()),
+                    Some(Greater) => {
+                        let (key, counter, weighted_counter, records) = other_iter.next().unwrap();
+                        elements.push(self.new_element(&key, counter, weighted_counter, records));
+                    },
+                    _ => {
+                        let element = self_iter.next().unwrap();
+                        let (_, counter, weighted_counter, records) = other_iter.next().unwrap();
+                        element.write().unwrap().absorb(counter, weighted_counter, records);
+                        elements.push(element);
+                    }
+                }
+            }
+        }
+        self.rebuild(elements);
+    }
+
+    /// Record ids follow the counters: `Min` keeps the records found in both graphs,
+    /// `Max` and `Sum` keep the records found in either.
+    pub fn intersection(&self, other: &Self, semantics: CounterSemantics) -> Self {
+        self.combine(other, |counter, other_counter| match (counter, other_counter) {
+            (Some(counter), Some(other_counter)) => Some(semantics.apply(counter, other_counter)),
+            _ => None
+        }, |records, other_records| match (records, other_records) {
+            (Some(records), Some(other_records)) if semantics == CounterSemantics::Min => records & other_records,
+            (Some(records), Some(other_records)) => records | other_records,
+            _ => BTreeSet::new()
+        })
+    }
+
+    /// Multiset difference: each key keeps its counter minus the other graph's counter
+    /// and is left out once that reaches zero. Weighted counters shrink in proportion.
+    /// Records found in the other graph are taken away.
+    pub fn difference(&self, other: &Self) -> Self {
+        self.combine(other, |counter, other_counter| match (counter, other_counter) {
+            (Some(counter), None) => Some(counter),
+            (Some(counter), Some(other_counter)) => subtract_counters(counter, other_counter),
+            _ => None
+        }, |records, other_records| match (records, other_records) {
+            (Some(records), Some(other_records)) => records - other_records,
+            (Some(records), None) => records.clone(),
+            _ => BTreeSet::new()
+        })
+    }
+
+    /// Multiset symmetric difference: a key in both graphs keeps the absolute
+    /// difference of its counters, taken from the graph with the larger counter.
+    /// Records found in only one of the graphs are kept.
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        self.combine(other, |counter, other_counter| match (counter, other_counter) {
+            (Some(counter), None) | (None, Some(counter)) => Some(counter),
+            (Some(counter), Some(other_counter)) => subtract_counters(counter, other_counter)
+                .or_else(|| subtract_counters(other_counter, counter)),
+            _ => None
+        }, |records, other_records| match (records, other_records) {
+            (Some(records), Some(other_records)) => records ^ other_records,
+            (Some(records), None) | (None, Some(records)) => records.clone(),
+            _ => BTreeSet::new()
+        })
+    }
+
+    // The counters passed to combine_counters are those of the occurrences inserted
+    // without a record id, and None stands for a key missing from that graph.
+    // Recorded occurrences are combined as sets by combine_records instead.
+    fn combine<F, R>(&self, other: &Self, mut combine_counters: F, mut combine_records: R) -> Self
+    where
+        F: FnMut(Option<(usize, f32)>, Option<(usize, f32)>) -> Option<(usize, f32)>,
+        R: FnMut(Option<&BTreeSet<usize>>, Option<&BTreeSet<usize>>) -> BTreeSet<usize>
+    {
+        let mut graph = Self::new(&self.name);
+        let mut elements = Vec::new();
+        let empty_records = BTreeSet::new();
+        let mut self_iter = self.iter().peekable();
+        let mut other_iter = other.iter().peekable();
+        loop {
+            let ordering = match (self_iter.peek(), other_iter.peek()) {
+                (Some(element), Some(other_element)) => {
+                    element.read().unwrap().key.partial_compare(&other_element.read().unwrap().key)
+                },
+                (Some(_), None) => Some(Less),
+                (None, Some(_)) => Some(Greater),
+                (None, None) => break
+            };
+            let (element, other_element) = match ordering {
+                Some(Less) => (self_iter.next(), None),
+                Some(Greater) => (None, other_iter.next()),
+                _ => (self_iter.next(), other_iter.next())
+            };
+            let (element, other_element) = (
+                element.as_ref().map(|element| element.read().unwrap()),
+                other_element.as_ref().map(|element| element.read().unwrap())
+            );
+            let (element, other_element) = (element.as_deref(), other_element.as_deref());
+            let (counter, weighted_counter) = combine_counters(
+                element.map(Element::anonymous_counters),
+                other_element.map(Element::anonymous_counters)
+            ).unwrap_or((0, 0.0f32));
+            let records = combine_records(
+                element.map(|element| element.records.as_ref().unwrap_or(&empty_records)),
+                other_element.map(|element| element.records.as_ref().unwrap_or(&empty_records))
+            );
+            if counter == 0 && records.is_empty() { continue }
+
+            let key = *dyn_clone::clone_box(&element.or(other_element).unwrap().key);
+            let recorded = records.len();
+            let records = if records.is_empty() { None } else { Some(records) };
+            elements.push(graph.new_element(
+                &key, counter + recorded, weighted_counter + recorded as f32, records
+            ));
+        }
+        graph.rebuild(elements);
+        graph
+    }
+
+    pub fn split_off(&mut self, key: &Key) -> Self {
+        let mut other = Self::new(&self.name);
+        if self.extreme_keys().is_none() { return other }
+
+        let root = (self.root.clone(), Node::height(&self.root));
+        let (left, right) = Node::split(&root.0, root.1, key, false);
+        self.root = left.0;
+        other.root = right.0;
+
+        let first = match Node::extreme_element(&other.root, false) {
+            Some(element) => element,
+            None => return other
+        };
+        first.write().unwrap().prev = None;
+        other.key_min = Some(*dyn_clone::clone_box(&first.read().unwrap().key));
+        other.element_min = Some(first);
+        other.key_max = self.key_max.take();
+        other.element_max = self.element_max.take();
+
+        match Node::extreme_element(&self.root, true) {
+            Some(last) => {
+                last.write().unwrap().next = None;
+                self.key_max = Some(*dyn_clone::clone_box(&last.read().unwrap().key));
+                self.element_max = Some(last);
+                self.update_elements_weights(self.key_range());
+            },
+            None => {
+                self.key_min = None;
+                self.element_min = None;
+            }
+        }
+        other.update_elements_weights(other.key_range());
+
+        other
+    }
+
+    pub fn remove_range<R: RangeBounds<Key>>(&mut self, range: R) -> usize {
+        if self.extreme_keys().is_none() { return 0 }
+
+        let root = (self.root.clone(), Node::height(&self.root));
+        let (left, rest) = match range.start_bound() {
+            Included(key) => Node::split(&root.0, root.1, key, false),
+            Excluded(key) => Node::split(&root.0, root.1, key, true),
+            Unbounded => (Self::empty_tree(), root)
+        };
+        let (middle, right) = match range.end_bound() {
+            Included(key) => Node::split(&rest.0, rest.1, key, true),
+            Excluded(key) => Node::split(&rest.0, rest.1, key, false),
+            Unbounded => (rest, Self::empty_tree())
+        };
+        self.root = Node::concat(left, right).0;
+
+        let first = match Node::extreme_element(&middle.0, false) {
+            Some(element) => element,
+            None => return 0
+        };
+        let last = Node::extreme_element(&middle.0, true).unwrap();
+        let prev_opt = first.write().unwrap().prev.take().and_then(|prev| prev.0.upgrade());
+        let next_opt = last.write().unwrap().next.take().and_then(|next| next.0.upgrade());
+
+        let mut counter = 0usize;
+        let mut element = first;
+        loop {
+            let next_element = {
+                let mut element = element.write().unwrap();
+                counter += element.counter;
+                element.prev = None;
+                element.next.take().and_then(|next| next.0.upgrade())
+            };
+            match next_element {
+                Some(next_element) => element = next_element,
+                None => break
+            }
+        }
+
+        match (&prev_opt, &next_opt) {
+            (Some(prev), Some(next)) => Element::link(prev, next, self.key_range()),
+            (Some(prev), None) => prev.write().unwrap().next = None,
+            (None, Some(next)) => next.write().unwrap().prev = None,
+            (None, None) => {}
+        }
+        if prev_opt.is_none() {
+            self.element_min = next_opt.clone();
+            self.key_min = next_opt.as_ref().map(|next| *dyn_clone::clone_box(&next.read().unwrap().key));
+        }
+        if next_opt.is_none() {
+            self.element_max = prev_opt.clone();
+            self.key_max = prev_opt.as_ref().map(|prev| *dyn_clone::clone_box(&prev.read().unwrap().key));
+        }
+        if (prev_opt.is_none() || next_opt.is_none()) && self.extreme_keys().is_some() {
+            self.update_elements_weights(self.key_range());
+        }
+
+        counter
+    }
+
+    pub fn key_range(&self) -> f32 {
+        if self.key_min.is_none() || self.key_max.is_none() { return f32::NAN }
+        let ret = self.key_min.as_ref().unwrap().distance(self.key_max.as_ref().unwrap()) as f32;
+        if ret == 0.0f32 { 1.0f32 } else { ret }
+    }
+
+    pub fn count_elements_unique(&self) -> usize { self.root.read().unwrap().count_unique }
+
+    pub fn count_elements_agg(&self) -> usize { self.root.read().unwrap().count_agg }
+
+    pub fn count_elements_weighted(&self) -> f32 { self.root.read().unwrap().weighted_count_agg }
+
+    pub fn rank(&self, key: &Key) -> usize { self.count_below(key, false).0 }
+
+    pub fn select(&self, index: usize) -> Option<Arc<RwLock<Element<Key, ORDER>>>> {
+        let mut index = index;
+        let mut node = self.root.clone();
+        loop {
+            let child = {
+                let node_ref = node.read().unwrap();
+                let mut child_index = node_ref.size;
+                for i in 0..node_ref.size {
+                    if !node_ref.is_leaf {
+                        let child_count = node_ref.children[i].as_ref().unwrap().read().unwrap().count_unique;
+                        if index < child_count {
+                            child_index = i;
+                            break
+                        }
+                        index -= child_count;
+                    }
+                    if index == 0 { return Some(node_ref.elements[i].as_ref().unwrap().clone()) }
+                    index -= 1;
+                }
+                if node_ref.is_leaf { return None }
+                node_ref.children[child_index].as_ref().unwrap().clone()
+            };
+            node = child;
+        }
+    }
+
+    pub fn count_in_range<R: RangeBounds<Key>>(&self, range: R) -> usize {
+        let lower = match range.start_bound() {
+            Included(key) => self.count_below(key, false).0,
+            Excluded(key) => self.count_below(key, true).0,
+            Unbounded => 0
+        };
+        let upper = match range.end_bound() {
+            Included(key) => self.count_below(key, true).0,
+            Excluded(key) => self.count_below(key, false).0,
+            Unbounded => self.count_elements_unique()
+        };
+        upper.saturating_sub(lower)
+    }
+
+    fn count_below(&self, key: &Key, include_key: bool) -> (usize, f32) {
+        let (mut count_unique, mut weighted_count) = (0, 0.0f32);
+        let mut node = self.root.clone();
+        loop {
+            let child = {
+                let node_ref = node.read().unwrap();
+                let mut index = 0;
+                while index < node_ref.size {
+                    match node_ref.keys[index].as_ref().unwrap().partial_compare(key) {
+                        Some(Less) => {},
+                        Some(Equal) if include_key => {},
+                        _ => break
+                    }
+                    if !node_ref.is_leaf {
+                        let child = node_ref.children[index].as_ref().unwrap().read().unwrap();
+                        count_unique += child.count_unique;
+                        weighted_count += child.weighted_count_agg;
+                    }
+                    count_unique += 1;
+                    weighted_count += node_ref.elements[index].as_ref().unwrap().read().unwrap().weighted_counter;
+                    index += 1;
+                }
+                if node_ref.is_leaf { return (count_unique, weighted_count) }
+                node_ref.children[index].as_ref().unwrap().clone()
+            };
+            node = child;
+        }
+    }
+
+    /// Share of the weighted counters at or below `key`.
+    pub fn cdf(&self, key: &Key) -> f32 {
+        let weighted_count = self.count_elements_weighted();
+        if weighted_count <= 0.0f32 { return 0.0f32 }
+        self.count_below(key, true).1 / weighted_count
+    }
+
+    pub fn pmf(&self, key: &Key) -> f32 {
+        let weighted_count = self.count_elements_weighted();
+        match self.search(key) {
+            Some(element) => element.read().unwrap().weighted_counter / weighted_count,
+            None => 0.0f32
+        }
+    }
+
+    /// Same positions as `neural::graph::ASAGraph::quantile`.
+    pub fn quantile(&self, p: f32) -> Option<Arc<RwLock<Element<Key, ORDER>>>> {
+        let position = self.quantile_position(p)?;
+        self.select_weighted(position)
+    }
+
+    pub fn quantile_interpolated(&self, p: f32) -> Option<f64> {
+        let position = self.quantile_position(p)? as f64;
+        let lower = key_to_f64(&self.select_weighted(position.floor() as f32)?.read().unwrap().key)?;
+        let upper = key_to_f64(&self.select_weighted(position.ceil() as f32)?.read().unwrap().key)?;
+        Some(lower + (position - position.floor()) * (upper - lower))
+    }
+
+    fn quantile_position(&self, p: f32) -> Option<f32> {
+        if self.count_elements_unique() == 0 || !(0.0f32..=1.0f32).contains(&p) { return None }
+        Some((p * (self.count_elements_weighted() - 1.0f32)).max(0.0f32))
+    }
+
+    pub fn median(&self) -> Option<Arc<RwLock<Element<Key, ORDER>>>> { self.quantile(0.5f32) }
+
+    /// The mean, moments and mode weigh each key by its weighted counter.
+    pub fn stats(&self) -> ASAGraphStats<Key> {
+        let mut stats = ASAGraphStats {
+            count: self.count_elements_agg(),
+            weighted_count: self.count_elements_weighted(),
+            distinct: self.count_elements_unique(),
+            min: self.key_min.as_ref().map(|key| *dyn_clone::clone_box(key)),
+            max: self.key_max.as_ref().map(|key| *dyn_clone::clone_box(key)),
+            mean: None,
+            variance: None,
+            std: None,
+            skewness: None,
+            mode: None
+        };
+
+        let mut mode_counter = 0.0f32;
+        for element in self {
+            let element = element.read().unwrap();
+            if element.weighted_counter > mode_counter {
+                mode_counter = element.weighted_counter;
+                stats.mode = Some(*dyn_clone::clone_box(&element.key));
+            }
+        }
+
+        if stats.count == 0 || !matches!(self.data_category(), DataCategory::Numerical) { return stats }
+
+        let values: Vec<(f64, f64)> = self.iter().filter_map(|element| {
+            let element = element.read().unwrap();
+            Some((key_to_f64(&element.key)?, element.weighted_counter as f64))
+        }).collect();
+        let count = values.iter().map(|(_, counter)| counter).sum::<f64>();
+        let mean = values.iter().map(|(value, counter)| value * counter).sum::<f64>() / count;
+        let (m2, m3) = values.iter().fold((0.0f64, 0.0f64), |(m2, m3), (value, counter)| {
+            let deviation = value - mean;
+            (m2 + counter * deviation.powi(2), m3 + counter * deviation.powi(3))
+        });
+        let variance = m2 / count;
+        let std = variance.sqrt();
+
+        stats.mean = Some(mean);
+        stats.variance = Some(variance);
+        stats.std = Some(std);
+        stats.skewness = if std > 0.0f64 { Some(m3 / count / std.powi(3)) } else { None };
+        stats
+    }
+
+    pub fn histogram_equal_width(&self, bins: usize) -> Option<ASAGraphHistogram> {
+        if bins == 0 { return None }
+        let min = key_to_f64(self.key_min.as_ref()?)?;
+        let max = key_to_f64(self.key_max.as_ref()?)?;
+        let width = (max - min) / bins as f64;
+
+        let edges = (0..=bins)
+            .map(|i| if i == bins { max } else { min + i as f64 * width })
+            .collect();
+        let mut counts = vec![0.0f32; bins];
+        for element in self {
+            let element = element.read().unwrap();
+            let value = key_to_f64(&element.key)?;
+            let index = if width > 0.0f64 {
+                (((value - min) / width) as usize).min(bins - 1)
+            } else {
+                0
+            };
+            counts[index] += element.weighted_counter;
+        }
+
+        Some(ASAGraphHistogram { edges, counts })
+    }
+
+    /// Same bins as `neural::graph::ASAGraph::histogram_equal_frequency`.
+    pub fn histogram_equal_frequency(&self, bins: usize) -> Option<ASAGraphHistogram> {
+        let weighted_count = self.count_elements_weighted();
+        if bins == 0 || self.count_elements_unique() == 0 { return None }
+
+        let mut edges = vec![self.element_min.clone()?];
+        for i in 1..bins {
+            let element = self.select_weighted(i as f32 * weighted_count / bins as f32)?;
+            if !Arc::ptr_eq(&element, edges.last().unwrap()) { edges.push(element) }
+        }
+        let element_max = self.element_max.clone()?;
+        if edges.len() > 1 && Arc::ptr_eq(&element_max, edges.last().unwrap()) { edges.pop(); }
+        edges.push(element_max);
+
+        let mut counts = Vec::with_capacity(edges.len() - 1);
+        let mut lower = 0.0f32;
+        for i in 1..edges.len() {
+            let upper = if i == edges.len() - 1 {
+                weighted_count
+            } else {
+                self.count_below(&edges[i].read().unwrap().key, false).1
+            };
+            counts.push(upper - lower);
+            lower = upper;
+        }
+
+        let edges = edges.iter()
+            .map(|element| key_to_f64(&element.read().unwrap().key))
+            .collect::<Option<Vec<f64>>>()?;
+        Some(ASAGraphHistogram { edges, counts })
+    }
+
+    // The element whose weighted counter covers `position` in the running total;
+    // positions past the total fall on the last element.
+    fn select_weighted(&self, position: f32) -> Option<Arc<RwLock<Element<Key, ORDER>>>> {
+        let mut position = position;
+        let mut node = self.root.clone();
+        loop {
+            let child = {
+                let node_ref = node.read().unwrap();
+                let mut child_index = node_ref.size;
+                for i in 0..node_ref.size {
+                    if !node_ref.is_leaf {
+                        let child_weight = node_ref.children[i].as_ref().unwrap().read().unwrap().weighted_count_agg;
+                        if position < child_weight {
+                            child_index = i;
+                            break
+                        }
+                        position -= child_weight;
+                    }
+                    let element = node_ref.elements[i].as_ref().unwrap();
+                    let weighted_counter = element.read().unwrap().weighted_counter;
+                    if position < weighted_counter { return Some(element.clone()) }
+                    position -= weighted_counter;
+                }
+                if node_ref.is_leaf { return self.element_max.clone() }
+                node_ref.children[child_index].as_ref().unwrap().clone()
+            };
+            node = child;
+        }
+    }
+
+    fn extreme_keys(&self) -> Option<(&Key, &Key)> {
+        if self.key_min.is_none() || self.key_max.is_none() { return None }
+        let key_min = self.key_min.as_ref().unwrap();
+        let key_max = self.key_max.as_ref().unwrap();
+        Some((key_min, key_max))
+    }
+
+    fn insert_first_element(
+        &mut self, node: &Arc<RwLock<Node<Key, ORDER>>>, key: &Key
+    ) -> Arc<RwLock<Element<Key, ORDER>>> {
+        let element_pointer = Element::<Key, ORDER>::new(key, &self.name);
+        {
+            let mut node = node.write().unwrap();
+            node.elements[0] = Some(element_pointer.clone());
+            node.keys[0] = Some(*dyn_clone::clone_box(key));
+            node.size = 1;
+            node.update_counts();
+        }
+
+        self.key_min = Some(*dyn_clone::clone_box(key));
+        self.key_max = Some(*dyn_clone::clone_box(key));
+        self.element_min = Some(element_pointer.clone());
+        self.element_max = Some(element_pointer.clone());
+
+        element_pointer
+    }
+
+    fn new_element(
+        &self, key: &Key, counter: usize, weighted_counter: f32, records: Option<BTreeSet<usize>>
+    ) -> Arc<RwLock<Element<Key, ORDER>>> {
+        let element = Element::new(key, &self.name);
+        {
+            let mut element = element.write().unwrap();
+            element.counter = counter;
+            element.weighted_counter = weighted_counter;
+            element.records = records;
+        }
+        element
+    }
+
+    fn rebuild(&mut self, elements: Vec<Arc<RwLock<Element<Key, ORDER>>>>) {
+        self.root = Node::build(&elements);
+
+        let (first, last) = match (elements.first(), elements.last()) {
+            (Some(first), Some(last)) => (first.clone(), last.clone()),
+            _ => {
+                self.element_min = None;
+                self.element_max = None;
+                self.key_min = None;
+                self.key_max = None;
+                return
+            }
+        };
+        first.write().unwrap().prev = None;
+        last.write().unwrap().next = None;
+        self.key_min = Some(*dyn_clone::clone_box(&first.read().unwrap().key));
+        self.key_max = Some(*dyn_clone::clone_box(&last.read().unwrap().key));
+        self.element_min = Some(first);
+        self.element_max = Some(last);
+
+        let range = self.key_range();
+        for pair in elements.windows(2) { Element::link(&pair[0], &pair[1], range); }
+    }
+
+    fn empty_tree() -> (Arc<RwLock<Node<Key, ORDER>>>, usize) {
+        (Arc::new(RwLock::new(Node::new(true, None))), 0)
+    }
+
+    fn split_root(&mut self) -> Arc<RwLock<Node<Key, ORDER>>> {
+        let new_root = Arc::new(RwLock::new(Node::new(false, None)));
+        let old_root = self.root.clone();
+        self.root = new_root;
+        old_root.write().unwrap().parent = Some(Arc::downgrade(&self.root));
+        self.root.write().unwrap().children[0] = Some(old_root);
+        Node::split_child(&self.root, 0);
+        self.root.clone()
+    }
+
+    fn shrink_root(&mut self) {
+        let new_root = {
+            let mut root = self.root.write().unwrap();
+            if root.size > 0 || root.is_leaf { return }
+            root.children[0].take().unwrap()
+        };
+        new_root.write().unwrap().parent = None;
+        self.root = new_root;
+    }
+
+    fn set_extrema(&mut self, element: &Arc<RwLock<Element<Key, ORDER>>>) {
+        let key = *dyn_clone::clone_box(&element.read().unwrap().key);
+        let mut should_update_weights = false;
+        match (&self.key_min, &self.key_max) {
+            (Some(key_min), Some(key_max)) => {
+                if key.partial_compare(key_min) == Some(Less) {
+                    self.key_min = Some(*dyn_clone::clone_box(&key));
+                    self.element_min = Some(element.clone());
+                    should_update_weights = true;
+                }
+                if key.partial_compare(key_max) == Some(Greater) {
+                    self.key_max = Some(key);
+                    self.element_max = Some(element.clone());
+                    should_update_weights = true;
+                }
+            },
+            (None, None) => {
+                self.key_min = Some(*dyn_clone::clone_box(&key));
+                self.key_max = Some(key);
+                self.element_min = Some(element.clone());
+                self.element_max = Some(element.clone());
+                should_update_weights = true;
+            },
+            _ => panic!("inconsistent extremas: key_min.is_none() != key_max.is_none()")
+        }
+
+        if should_update_weights { self.update_elements_weights(self.key_range()); }
+    }
+
+    fn unset_extrema(
+        &mut self,
+        element: &Arc<RwLock<Element<Key, ORDER>>>,
+        prev_opt: Option<&Arc<RwLock<Element<Key, ORDER>>>>,
+        next_opt: Option<&Arc<RwLock<Element<Key, ORDER>>>>
+    ) {
+        let mut should_update_weights = false;
+        if self.element_min.as_ref().is_some_and(|element_min| Arc::ptr_eq(element_min, element)) {
+            self.element_min = next_opt.cloned();
+            self.key_min = next_opt.map(|next| *dyn_clone::clone_box(&next.read().unwrap().key));
+            should_update_weights = true;
+        }
+        if self.element_max.as_ref().is_some_and(|element_max| Arc::ptr_eq(element_max, element)) {
+            self.element_max = prev_opt.cloned();
+            self.key_max = prev_opt.map(|prev| *dyn_clone::clone_box(&prev.read().unwrap().key));
+            should_update_weights = true;
+        }
+
+        if should_update_weights && self.extreme_keys().is_some() {
+            self.update_elements_weights(self.key_range());
+        }
+    }
+
+    fn update_elements_weights(&mut self, range: f32) {
+        let mut element_opt = self.element_min.clone();
+        while let Some(element) = element_opt {
+            let next_opt = element.read().unwrap().next.as_ref().and_then(|next| next.0.upgrade());
+            if let Some(next) = &next_opt { Element::link(&element, next, range); }
+            element_opt = next_opt;
+        }
+    }
+}
+
+impl<Key, const ORDER: usize> ASAGraph<Key, ORDER>
+where
+    Key: SensorData + Send + Sync,
+    [(); ORDER + 1]:,
+    PhantomData<Key>: DataDeductor,
+    DataTypeValue: From<Key>
+{
+    /// Builds a `neural::graph::ASAGraph` on `threads` workers and moves its elements over.
+    pub fn new_parallel(name: &str, data: &[Key], threads: usize) -> Self {
+        Self::from_neural(&neural::graph::ASAGraph::new_parallel(name, data, threads))
+    }
+
+    pub fn new_parallel_records(name: &str, data: &[(Key, usize)], threads: usize) -> Self {
+        Self::from_neural(&neural::graph::ASAGraph::new_parallel_records(name, data, threads))
+    }
+
+    fn from_neural(source: &neural::graph::ASAGraph<Key, ORDER>) -> Self {
+        let mut graph = Self::new(&source.name);
+        let elements = source.iter()
+            .map(|element| {
+                let element = element.borrow();
+                graph.new_element(&element.key, element.counter, element.weighted_counter, element.records.clone())
+            })
+            .collect();
+        graph.rebuild(elements);
+        graph
+    }
+}
+
+impl<'a, Key, const ORDER: usize> IntoIterator for &'a ASAGraph<Key, ORDER>
+where Key: SensorData, [(); ORDER + 1]: {
+    type Item = Arc<RwLock<Element<Key, ORDER>>>;
+    type IntoIter = ASAGraphIntoIterator<'a, Key, ORDER>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ASAGraphIntoIterator {
+            graph: self,
+            front: self.element_min.clone(),
+            back: self.element_max.clone()
+        }
+    }
+}
+
+pub struct ASAGraphIntoIterator<'a, Key, const ORDER: usize = 25>
+where Key: SensorData, [(); ORDER + 1]: {
+    graph: &'a ASAGraph<Key, ORDER>,
+    front: Option<Arc<RwLock<Element<Key, ORDER>>>>,
+    back: Option<Arc<RwLock<Element<Key, ORDER>>>>
+}
+
+impl<'a, Key, const ORDER: usize> Iterator for ASAGraphIntoIterator<'a, Key, ORDER>
+where Key: SensorData, [(); ORDER + 1]: {
+    type Item = Arc<RwLock<Element<Key, ORDER>>>;
+    fn next(&mut self) -> Option<Arc<RwLock<Element<Key, ORDER>>>> {
+        let element = self.front.take()?;
+
+        if Arc::ptr_eq(&element, self.back.as_ref().unwrap()) {
+            self.back = None;
+        } else {
+            self.front = element.read().unwrap().next.as_ref().and_then(|next| next.0.upgrade());
+        }
+
+        Some(element)
+    }
+}
+
+impl<'a, Key, const ORDER: usize> DoubleEndedIterator for ASAGraphIntoIterator<'a, Key, ORDER>
+where Key: SensorData, [(); ORDER + 1]: {
+    fn next_back(&mut self) -> Option<Arc<RwLock<Element<Key, ORDER>>>> {
+        let element = self.back.take()?;
+
+        if Arc::ptr_eq(&element, self.front.as_ref().unwrap()) {
+            self.front = None;
+        } else {
+            self.back = element.read().unwrap().prev.as_ref().and_then(|prev| prev.0.upgrade());
+        }
+
+        Some(element)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::{ sync::Arc, thread, collections::BTreeMap, ops::Bound::Excluded };
+
+    use super::{ ASAGraph, CounterSemantics, super::node::{ Node, LockCell } };
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn send_sync() {
+        assert_send_sync::<ASAGraph<i32, 3>>();
+    }
+
+    #[test]
+    fn insert_search_remove() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 0..100 { graph.insert(&(i % 40)); }
+
+        assert_eq!(graph.count_elements_unique(), 40);
+        assert_eq!(graph.count_elements_agg(), 100);
+        assert_eq!(graph.search(&7).unwrap().read().unwrap().counter, 3);
+        assert!(graph.search(&40).is_none());
+        assert_eq!(graph.key_min, Some(0));
+        assert_eq!(graph.key_max, Some(39));
+
+        let element = graph.search(&10).unwrap();
+        let (prev, weight) = element.read().unwrap().prev.clone().unwrap();
+        assert_eq!(prev.upgrade().unwrap().read().unwrap().key, 9);
+        assert_eq!(weight, 1.0f32 - 1.0f32 / 39.0f32);
+
+        assert_eq!(graph.remove(&7), Some(2));
+        assert_eq!(graph.remove_all(&39).unwrap().read().unwrap().key, 39);
+        assert_eq!(graph.remove(&100), None);
+        assert_eq!(graph.key_max, Some(38));
+        let (_, weight) = element.read().unwrap().prev.clone().unwrap();
+        assert_eq!(weight, 1.0f32 - 1.0f32 / 38.0f32);
+
+        assert_eq!(graph.rank(&10), 10);
+        assert_eq!(graph.select(10).unwrap().read().unwrap().key, 10);
+        assert_eq!(graph.count_in_range(5..=15), 11);
+        let keys = graph.range(30..).map(|e| e.read().unwrap().key).collect::<Vec<_>>();
+        assert_eq!(keys, (30..39).collect::<Vec<_>>());
+        let keys = graph.iter().rev().take(2).map(|e| e.read().unwrap().key).collect::<Vec<_>>();
+        assert_eq!(keys, vec![38, 37]);
+    }
+
+    #[test]
+    fn concurrent_readers_and_writer() {
+        let graph = ASAGraph::<i32, 25>::new_arc("test");
+        {
+            let mut graph = graph.write().unwrap();
+            for i in 0..1_000 { graph.insert(&i); }
+        }
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                let graph = Arc::clone(&graph);
+                scope.spawn(move || {
+                    for i in 0..1_000 {
+                        let graph = graph.read().unwrap();
+                        assert_eq!(graph.search(&i).unwrap().read().unwrap().key, i);
+                        assert_eq!(graph.rank(&i), i as usize);
+                    }
+                });
+            }
+            let graph = Arc::clone(&graph);
+            scope.spawn(move || {
+                for i in 1_000..2_000 { graph.write().unwrap().insert(&i); }
+            });
+        });
+
+        let graph = graph.read().unwrap();
+        assert_eq!(graph.count_elements_unique(), 2_000);
+        assert_eq!(graph.iter().count(), 2_000);
+    }
+
+    #[test]
+    fn move_into_worker() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        graph.insert(&1);
+        let graph = thread::spawn(move || {
+            for i in 2..=50 { graph.insert(&i); }
+            graph
+        }).join().unwrap();
+        assert_eq!(graph.count_elements_unique(), 50);
+        assert_eq!(graph.key_max, Some(50));
+    }
+
+    #[test]
+    fn distribution() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        assert_eq!(graph.cdf(&1), 0.0f32);
+        assert!(graph.median().is_none() && graph.histogram_equal_width(4).is_none());
+        for i in 0..=10 { graph.insert(&i); }
+        graph.insert_n(&10, 5);
+
+        assert_eq!(graph.cdf(&9), 10.0f32 / 16.0f32);
+        assert_eq!(graph.pmf(&10), 6.0f32 / 16.0f32);
+        assert_eq!(graph.quantile(0.0f32).unwrap().read().unwrap().key, 0);
+        assert_eq!(graph.median().unwrap().read().unwrap().key, 7);
+        assert_eq!(graph.quantile_interpolated(1.0f32), Some(10.0f64));
+        assert_eq!(graph.stats().mode, Some(10));
+
+        let histogram = graph.histogram_equal_width(4).unwrap();
+        assert_eq!(histogram.edges, vec![0.0f64, 2.5f64, 5.0f64, 7.5f64, 10.0f64]);
+        assert_eq!(histogram.counts, vec![3.0f32, 2.0f32, 3.0f32, 8.0f32]);
+        let histogram = graph.histogram_equal_frequency(4).unwrap();
+        assert_eq!(histogram.edges, vec![0.0f64, 4.0f64, 8.0f64, 10.0f64]);
+        assert_eq!(histogram.counts, vec![4.0f32, 4.0f32, 8.0f32]);
+
+        let stats = ASAGraph::<i32, 3>::new_from_vec("test", &[2, 4, 4, 4, 5, 5, 7, 9]).stats();
+        assert_eq!((stats.mean, stats.std), (Some(5.0f64), Some(2.0f64)));
+    }
+
+    #[test]
+    fn records() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for record in 0..60 { graph.insert_record(&(record as i32 % 20), record); }
+        graph.insert(&5);
+
+        assert_eq!(graph.search(&5).unwrap().read().unwrap().counter(), 4);
+        assert_eq!(graph.records(&5).into_iter().collect::<Vec<_>>(), vec![5, 25, 45]);
+        assert_eq!(graph.insert_record(&5, 25).read().unwrap().counter(), 4);
+        assert_eq!(graph.records_in_range(3..5).len(), 6);
+
+        assert_eq!(graph.remove(&5), Some(3));
+        assert_eq!(graph.remove(&5), None);
+        assert!(graph.remove_record(&5, 25));
+        assert!(!graph.remove_record(&5, 25));
+        assert_eq!(graph.search(&5).unwrap().read().unwrap().counter(), 2);
+        assert_eq!(graph.validate(), Ok(()));
+
+        let mut other = ASAGraph::<i32, 3>::new("other");
+        other.insert_record(&4, 100);
+        other.insert_record(&200, 101);
+        let intersection = graph.intersection(&other, CounterSemantics::Sum);
+        assert_eq!(intersection.records(&4).into_iter().collect::<Vec<_>>(), vec![4, 24, 44, 100]);
+        assert!(graph.intersection(&other, CounterSemantics::Min).records(&4).is_empty());
+        graph.merge(&other);
+        assert_eq!(graph.records(&4).len(), 4);
+        let rest = graph.split_off(&10);
+        assert_eq!(rest.records_in_range(..).len(), 31);
+        assert_eq!(graph.validate(), Ok(()));
+        assert_eq!(rest.validate(), Ok(()));
+    }
+
+    #[test]
+    fn new_parallel() {
+        let data = (0..2_000).map(|i| (i * 7 % 300, i as usize)).collect::<Vec<_>>();
+        let bulk = ASAGraph::<i32, 4>::new_bulk_records("test", &data);
+        assert_eq!(bulk.validate(), Ok(()));
+        let elements = |graph: &ASAGraph<i32, 4>| graph.iter()
+            .map(|e| {
+                let e = e.read().unwrap();
+                (e.key, e.counter(), e.records().cloned())
+            })
+            .collect::<Vec<_>>();
+
+        for threads in [1, 3, 8] {
+            let parallel = ASAGraph::<i32, 4>::new_parallel_records("test", &data, threads);
+            assert_eq!(parallel.validate(), Ok(()));
+            assert_eq!(elements(&parallel), elements(&bulk));
+        }
+        let keys = data.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+        let parallel = ASAGraph::<i32, 4>::new_parallel("test", &keys, 4);
+        assert_eq!(parallel.count_elements_agg(), 2_000);
+        assert_eq!(parallel.count_elements_unique(), 300);
+    }
+
+    graph_tests!(ASAGraph);
+
+    differential_tests!(ASAGraph, |graph: &mut ASAGraph<i32, ORDER>, model: &mut BTreeMap<i32, usize>, key: i32, is_all: bool| {
+        let counter = model.get(&key).cloned();
+        if is_all {
+            assert_eq!(graph.remove_all(&key).map(|e| e.read().unwrap().counter()), counter, "remove_all {}", key);
+            model.remove(&key);
+        } else {
+            assert_eq!(graph.remove(&key), counter.map(|counter| counter - 1), "remove {}", key);
+            match counter {
+                Some(1) => { model.remove(&key); },
+                Some(counter) => { model.insert(key, counter - 1); },
+                None => {}
+            }
+        }
+    });
+}
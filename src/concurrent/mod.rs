@@ -0,0 +1,15 @@
+//! `Send + Sync` ASA graph with `Arc<RwLock<..>>` nodes and elements.
+//!
+//! This is a port of the ordered multiset part of `neural::graph::ASAGraph`: bulk and parallel
+//! construction, weighted and recorded insertion, removal, neighbour and range queries,
+//! set operations, split/remove of ranges, distribution statistics and validation. The node
+//! algorithms are the ones the neural and simple graphs expand from `node_algorithms!`.
+//! Neural activation and element definitions are not ported, because `bionet_common`
+//! connects neurons as `Rc<RefCell<dyn Neuron>>`, which cannot cross threads. Entries,
+//! cursors and snapshots are not ported either: readers share the graph behind its lock.
+//! Elements still keep their weighted links to neighbours.
+
+pub mod graph;
+pub mod node;
+pub mod element;
+pub mod validation;
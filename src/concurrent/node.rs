@@ -0,0 +1,272 @@
+use std::{
+    fmt::{ Display, Formatter, Result },
+    sync::{ Arc, Weak, RwLock, RwLockReadGuard, RwLockWriteGuard },
+    cmp::{ Ordering, Ordering::* },
+    marker::PhantomData
+};
+
+use bionet_common::{
+    sensor::SensorData,
+    data::{ DataDeductor, DataTypeValue }
+};
+
+use super::element::Element;
+
+// Gives `RwLock` the `RefCell` borrow methods the shared node algorithms are written with.
+// Writers are serialized by the lock around the graph, so a poisoned node lock means
+// a writer panicked halfway through and the tree can't be trusted anymore.
+pub(crate) trait LockCell<T> {
+    fn borrow(&self) -> RwLockReadGuard<'_, T>;
+
+    fn borrow_mut(&self) -> RwLockWriteGuard<'_, T>;
+}
+
+impl<T> LockCell<T> for RwLock<T> {
+    fn borrow(&self) -> RwLockReadGuard<'_, T> { self.read().unwrap() }
+
+    fn borrow_mut(&self) -> RwLockWriteGuard<'_, T> { self.write().unwrap() }
+}
+
+#[derive(Clone)]
+pub struct Node<Key, const ORDER: usize>
+where Key: SensorData, [(); ORDER + 1]: {
+    pub(crate) size: usize,
+    pub(crate) is_leaf: bool,
+    pub(crate) count_unique: usize,
+    pub(crate) count_agg: usize,
+    pub(crate) weighted_count_agg: f32,
+    pub(crate) parent: Option<Weak<RwLock<Node<Key, ORDER>>>>,
+    pub(crate) keys: [Option<Key>; ORDER],
+    pub(crate) elements: [Option<Arc<RwLock<Element<Key, ORDER>>>>; ORDER],
+    pub(crate) children: [Option<Arc<RwLock<Node<Key, ORDER>>>>; ORDER + 1]
+}
+
+impl<Key, const ORDER: usize> Node<Key, ORDER> 
+where 
+    Key: SensorData, 
+    [(); ORDER + 1]:, 
+    PhantomData<Key>: DataDeductor,
+    DataTypeValue: From<Key>
+{
+    pub fn new(
+        is_leaf: bool, parent: Option<Weak<RwLock<Node<Key, ORDER>>>>
+    ) -> Node<Key, ORDER> {
+        Node {
+            size: 0,
+            is_leaf,
+            count_unique: 0,
+            count_agg: 0,
+            weighted_count_agg: 0.0f32,
+            parent,
+            keys: [(); ORDER].map(|_| None),
+            elements: [(); ORDER].map(|_| None),
+            children: [(); ORDER + 1].map(|_| None)
+        }
+    }
+
+
+    pub fn split_child(node_ptr: &Arc<RwLock<Node<Key, ORDER>>>, child_index: usize) {
+        let node_size = node_ptr.write().unwrap().size;
+
+        let left_node = 
+            node_ptr.read().unwrap().children[child_index].as_ref().unwrap().clone();
+        left_node.write().unwrap().parent = Some(Arc::downgrade(node_ptr));
+
+        let mut right_node = Node::<Key, ORDER>::new(
+            left_node.read().unwrap().is_leaf, Some(Arc::downgrade(node_ptr))
+        );
+        right_node.parent = Some(Arc::downgrade(node_ptr));
+        
+        let right_size = ORDER - Self::T_OFFSET;
+        right_node.size = right_size;
+        left_node.write().unwrap().size = Self::MID_INDEX;
+
+        for i in 0..right_size {
+            right_node.elements[i] = left_node.write().unwrap().elements[Self::T_OFFSET + i].take();
+            right_node.keys[i] = left_node.write().unwrap().keys[Self::T_OFFSET + i].take();
+        }
+
+        for i in ((child_index + 1)..=(node_size)).rev() {
+            node_ptr.write().unwrap().children.swap(i, i + 1);
+        }
+        for i in ((child_index as isize)..=(node_size as isize - 1isize)).rev() {
+            node_ptr.write().unwrap().elements.swap(i as usize, (i + 1) as usize);
+            node_ptr.write().unwrap().keys.swap(i as usize, (i + 1) as usize);
+        }
+
+        node_ptr.write().unwrap().children[child_index + 1] = Some(Arc::new(RwLock::new(right_node)));
+        let right_node = 
+            node_ptr.read().unwrap().children[child_index + 1].as_ref().unwrap().clone();
+        
+        if !left_node.read().unwrap().is_leaf {
+            for i in 0..=right_size {
+                right_node.write().unwrap().children[i] = 
+                    left_node.write().unwrap().children[Self::T_OFFSET + i].take();
+                right_node.write().unwrap().children[i].as_ref().unwrap().write().unwrap().parent 
+                    = Some(Arc::downgrade(&right_node));
+            }
+        }
+
+        node_ptr.write().unwrap().elements[child_index] = 
+            left_node.write().unwrap().elements[Self::MID_INDEX].take();
+        node_ptr.write().unwrap().keys[child_index] = 
+            left_node.write().unwrap().keys[Self::MID_INDEX].take();
+        node_ptr.write().unwrap().size += 1;
+
+        left_node.write().unwrap().update_counts();
+        right_node.write().unwrap().update_counts();
+        node_ptr.write().unwrap().update_counts();
+    }
+
+    pub(crate) fn insert_existing_key(&self, key: &Key, left_search: bool)
+    -> (Option<Arc<RwLock<Element<Key, ORDER>>>>, usize) {
+        let mut index;
+        if left_search {
+            index = 0usize;
+            let mut current_key = self.keys[index].as_ref().unwrap();
+            while index < self.size - 1 && key.partial_compare(current_key) == Some(Greater) {
+                index += 1;
+                current_key = self.keys[index].as_ref().unwrap();
+            }
+            if key.partial_compare(current_key) == Some(Greater) {
+                index += 1;
+            }
+            if index < self.size && key.equals(current_key) {
+                let element = self.elements[index].as_ref().unwrap().clone();
+                element.write().unwrap().counter += 1;
+                element.write().unwrap().weighted_counter += 1.0f32;
+                return (Some(element), index)
+            }
+        } else {
+            index = self.size - 1;
+            let mut current_key = self.keys[index].as_ref().unwrap();
+            while index > 0 && key.partial_compare(current_key) == Some(Less)  {
+                index -= 1;
+                current_key = self.keys[index].as_ref().unwrap();
+            }
+            if key.partial_compare(current_key) == Some(Greater) {
+                index += 1;
+            } else if key.equals(current_key) {
+                let element = self.elements[index].as_ref().unwrap().clone();
+                element.write().unwrap().counter += 1;
+                element.write().unwrap().weighted_counter += 1.0f32;
+                return (Some(element), index)
+            }
+
+            if index < self.size && key.equals(current_key) {
+                let element = self.elements[index].as_ref().unwrap().clone();
+                element.write().unwrap().counter += 1;
+                element.write().unwrap().weighted_counter += 1.0f32;
+                return (Some(element), index)
+            }
+        }
+        (None, index)
+    }
+
+    pub(crate) fn insert_key_leaf(
+        node: &Arc<RwLock<Node<Key, ORDER>>>, 
+        key: &Key, 
+        parent: &Arc<str>,
+        range: f32
+    ) -> Arc<RwLock<Element<Key, ORDER>>> {
+        let node_size = node.read().unwrap().size;
+
+        let mut index = node_size - 1;
+        let mut counter = node_size as isize - 1;
+        let mut should_move = false;
+        while counter >= 0 && key.partial_compare(node.read().unwrap().keys[counter as usize].as_ref().unwrap()) == Some(Less) {
+            should_move = true;
+            index = counter as usize;
+            counter -= 1;
+        }
+
+        if should_move {
+            for i in (index..node_size).rev() {
+                node.write().unwrap().elements.swap(i, i + 1);
+                node.write().unwrap().keys.swap(i, i + 1);
+            }
+        } else {
+            index += 1;
+        }
+        
+        let new_element = Element::new(key, parent);
+        node.write().unwrap().elements[index] = Some(new_element.clone());
+        node.write().unwrap().keys[index] = Some(*dyn_clone::clone_box(key));
+
+        let mut next_ptr = None;
+        let mut prev_ptr = None;
+        if node_size >= 1 {
+            if index == 0 {
+                if let Some(next) = node.read().unwrap().elements[1].as_ref() {
+                    next_ptr = Some(next.clone());
+                    prev_ptr = next.as_ref().read().unwrap().prev.as_ref().map(|v| v.0.upgrade().unwrap());
+                }
+            } else {
+                if let Some(prev) = node.read().unwrap().elements[index - 1].as_ref() {
+                    prev_ptr = Some(prev.clone());
+                    next_ptr = prev.as_ref().read().unwrap().next.as_ref().map(|e| e.0.upgrade().unwrap().clone());
+                }
+            }
+        }
+
+        Element::<Key, ORDER>::set_connections(
+            &new_element, prev_ptr.as_ref(), next_ptr.as_ref(), range
+        );
+        node.write().unwrap().size += 1;
+        Self::update_counts_upwards(node);
+
+        new_element
+    }
+
+    fn key_ordering(key: &Key, other: &Key) -> Option<Ordering> { key.partial_compare(other) }
+
+    fn clone_key(key: &Key) -> Key { *dyn_clone::clone_box(key) }
+
+    // There is no snapshot cache: readers share the graph itself.
+    fn clear_frozen(&mut self) {}
+
+    node_algorithms!(Arc, RwLock, Element<Key, ORDER>);
+
+    pub const MIN_CHILDREN: usize = ORDER.div_ceil(2);
+    pub const MAX_CHILDREN: usize = ORDER + 1;
+    pub const MIN_ELEMENTS: usize = ORDER.div_ceil(2) - 1;
+    pub const MAX_ELEMENTS: usize = ORDER;
+    pub const MIN_KEYS: usize = ORDER.div_ceil(2) - 1;
+    pub const MAX_KEYS: usize = ORDER;
+    pub(crate) const MID_INDEX: usize = ORDER.div_ceil(2) - 1;
+    pub(crate) const T_OFFSET: usize = ORDER.div_ceil(2);
+}
+
+impl<Key, const ORDER: usize> Display for Node<Key, ORDER> 
+where Key: SensorData, [(); ORDER + 1]: {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let mut node: String = format!(
+            "<==leaf:{}, size:{}, keys:", self.is_leaf, self.size
+        );
+        for key in &self.keys {
+            let element: String = match key.as_ref() {
+                Some(k) => format!("({k})"),
+                None => "()".to_string()
+            };
+            node.push_str(&element);
+        }
+        node.push_str(", elements:");
+        for i in 0..self.elements.len() {
+            let element: String = match self.elements[i].as_ref() {
+                Some(e) => format!("{}", e.as_ref().read().unwrap()),
+                None => "[]".to_string()
+            };
+            node.push_str(&element);
+        }
+        node.push_str(", children:");
+        for child in &self.children {
+            let child_node: String = match child.as_ref() {
+                Some(n) => format!("{}", n.as_ref().read().unwrap()),
+                None => "<>".to_string()
+            };
+            node.push_str(&child_node);
+        }
+        node.push_str("==>");
+        write!(f, "{}", node)
+    }
+}
\ No newline at end of file
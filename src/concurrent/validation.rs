@@ -0,0 +1,221 @@
+use std::{
+    sync::{ Arc, RwLock },
+    cmp::Ordering::*,
+    marker::PhantomData
+};
+
+use bionet_common::{
+    sensor::SensorData,
+    data::{ DataDeductor, DataTypeValue }
+};
+
+use super::{
+    element::Element,
+    node::Node,
+    graph::ASAGraph
+};
+
+pub use crate::neural::validation::ValidationError;
+
+const WEIGHT_TOLERANCE: f32 = 1.0e-5;
+
+fn is_same<T>(element: Option<&Arc<T>>, expected: Option<&Arc<T>>) -> bool {
+    match (element, expected) {
+        (Some(element), Some(expected)) => Arc::ptr_eq(element, expected),
+        (None, None) => true,
+        _ => false
+    }
+}
+
+impl<Key, const ORDER: usize> ASAGraph<Key, ORDER>
+where
+    Key: SensorData,
+    [(); ORDER + 1]:,
+    PhantomData<Key>: DataDeductor,
+    DataTypeValue: From<Key>
+{
+    pub fn validate(&self) -> Result<(), Vec<ValidationError<Key>>> {
+        let mut errors = Vec::new();
+        let mut elements = Vec::new();
+        let mut leaf_depth = None;
+
+        if self.root.read().unwrap().parent.is_some() {
+            errors.push(ValidationError::ParentPointer { depth: 0, index: 0 });
+        }
+        Self::validate_node(&self.root, 0, &mut leaf_depth, &mut elements, &mut errors);
+
+        for pair in elements.windows(2) {
+            let (key, next_key) = (&pair[0].read().unwrap().key, &pair[1].read().unwrap().key);
+            if key.partial_compare(next_key) != Some(Less) {
+                errors.push(ValidationError::UnsortedKeys {
+                    key: *dyn_clone::clone_box(key), next_key: *dyn_clone::clone_box(next_key)
+                });
+            }
+        }
+
+        self.validate_chain(&elements, &mut errors);
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    fn validate_node(
+        node: &Arc<RwLock<Node<Key, ORDER>>>,
+        depth: usize,
+        leaf_depth: &mut Option<usize>,
+        elements: &mut Vec<Arc<RwLock<Element<Key, ORDER>>>>,
+        errors: &mut Vec<ValidationError<Key>>
+    ) {
+        let node_ref = node.read().unwrap();
+        let size = node_ref.size;
+        let is_size_valid = if depth == 0 {
+            size <= Node::<Key, ORDER>::MAX_KEYS && (size > 0 || node_ref.is_leaf)
+        } else {
+            size >= Node::<Key, ORDER>::MIN_KEYS && size <= Node::<Key, ORDER>::MAX_KEYS
+        };
+        if !is_size_valid {
+            errors.push(ValidationError::NodeSize { depth, size });
+            if size > ORDER { return }
+        }
+
+        let first_element = elements.len();
+        let mut count_agg = 0;
+        for index in 0..=size {
+            if !node_ref.is_leaf {
+                match &node_ref.children[index] {
+                    Some(child) => {
+                        let parent = child.read().unwrap().parent.as_ref().and_then(|parent| parent.upgrade());
+                        if !parent.is_some_and(|parent| Arc::ptr_eq(&parent, node)) {
+                            errors.push(ValidationError::ParentPointer { depth, index });
+                        }
+                        Self::validate_node(child, depth + 1, leaf_depth, elements, errors);
+                        count_agg += child.read().unwrap().count_agg;
+                    },
+                    None => errors.push(ValidationError::MissingChild { depth, index })
+                }
+            }
+            if index == size { break }
+
+            let key = match &node_ref.keys[index] {
+                Some(key) => key,
+                None => continue
+            };
+            match &node_ref.elements[index] {
+                Some(element) => {
+                    let element_ref = element.read().unwrap();
+                    if !element_ref.key.equals(key) {
+                        errors.push(ValidationError::KeyMismatch {
+                            key: *dyn_clone::clone_box(key), element_key: *dyn_clone::clone_box(&element_ref.key)
+                        });
+                    }
+                    let (counter, records) = (element_ref.counter, element_ref.records().map_or(0, |r| r.len()));
+                    if records > counter {
+                        errors.push(ValidationError::Records { key: *dyn_clone::clone_box(key), counter, records });
+                    }
+                    count_agg += counter;
+                    elements.push(element.clone());
+                },
+                None => errors.push(ValidationError::MissingElement { key: *dyn_clone::clone_box(key) })
+            }
+        }
+
+        if node_ref.is_leaf {
+            match *leaf_depth {
+                Some(expected) if expected != depth => {
+                    errors.push(ValidationError::LeafDepth { expected, found: depth });
+                },
+                Some(_) => {},
+                None => *leaf_depth = Some(depth)
+            }
+        }
+
+        if node_ref.count_unique != elements.len() - first_element || node_ref.count_agg != count_agg {
+            errors.push(ValidationError::AggregateCounts { depth });
+        }
+    }
+
+    fn validate_chain(
+        &self,
+        elements: &[Arc<RwLock<Element<Key, ORDER>>>],
+        errors: &mut Vec<ValidationError<Key>>
+    ) {
+        if !is_same(self.element_min.as_ref(), elements.first()) { errors.push(ValidationError::ElementMin) }
+        if !is_same(self.element_max.as_ref(), elements.last()) { errors.push(ValidationError::ElementMax) }
+        let is_same_key = |key: &Option<Key>, element: Option<&Arc<RwLock<Element<Key, ORDER>>>>| {
+            match (key, element) {
+                (Some(key), Some(element)) => key.equals(&element.read().unwrap().key),
+                (None, None) => true,
+                _ => false
+            }
+        };
+        if !is_same_key(&self.key_min, elements.first()) { errors.push(ValidationError::KeyMin) }
+        if !is_same_key(&self.key_max, elements.last()) { errors.push(ValidationError::KeyMax) }
+
+        let range = self.key_range();
+        let mut chain_elements = 0;
+        let mut prev: Option<Arc<RwLock<Element<Key, ORDER>>>> = None;
+        let mut current = self.element_min.clone();
+        while let Some(element) = current {
+            if chain_elements == elements.len() || !Arc::ptr_eq(&element, &elements[chain_elements]) {
+                errors.push(ValidationError::BrokenChain { key: *dyn_clone::clone_box(&element.read().unwrap().key) });
+                break
+            }
+            let element_prev = element.read().unwrap().prev.as_ref().and_then(|(prev, _)| prev.upgrade());
+            if !is_same(element_prev.as_ref(), prev.as_ref()) {
+                errors.push(ValidationError::BrokenChain { key: *dyn_clone::clone_box(&element.read().unwrap().key) });
+            } else if let Some(prev) = &prev {
+                let expected = element.read().unwrap().weight(&prev.read().unwrap(), range);
+                let prev_weight = element.read().unwrap().prev.as_ref().map(|(_, weight)| *weight);
+                let next_weight = prev.read().unwrap().next.as_ref().map(|(_, weight)| *weight);
+                match (prev_weight, next_weight) {
+                    (Some(prev_weight), Some(next_weight)) => {
+                        for stored in [prev_weight, next_weight] {
+                            if (stored - expected).abs() > WEIGHT_TOLERANCE {
+                                let key = *dyn_clone::clone_box(&element.read().unwrap().key);
+                                errors.push(ValidationError::Weight { key, stored, expected });
+                            }
+                        }
+                    },
+                    _ => {
+                        let key = *dyn_clone::clone_box(&element.read().unwrap().key);
+                        errors.push(ValidationError::BrokenChain { key });
+                    }
+                }
+            }
+            chain_elements += 1;
+            current = element.read().unwrap().next.as_ref().and_then(|(next, _)| next.upgrade());
+            prev = Some(element);
+        }
+
+        if chain_elements != elements.len() {
+            errors.push(ValidationError::IncompleteChain { tree_elements: elements.len(), chain_elements });
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Weak;
+
+    use super::{ super::graph::ASAGraph, ValidationError };
+
+    #[test]
+    fn validate() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        assert_eq!(graph.validate(), Ok(()));
+        for i in 0..20 { graph.insert(&i); }
+        assert_eq!(graph.validate(), Ok(()));
+
+        graph.root.write().unwrap().count_agg += 1;
+        assert_eq!(graph.validate().unwrap_err(), vec![ValidationError::AggregateCounts { depth: 0 }]);
+        graph.root.write().unwrap().count_agg -= 1;
+
+        let element = graph.search(&5).unwrap();
+        let next = element.write().unwrap().next.take().unwrap();
+        element.write().unwrap().next = Some((Weak::new(), next.1));
+        let errors = graph.validate().unwrap_err();
+        assert_eq!(errors, vec![ValidationError::IncompleteChain { tree_elements: 20, chain_elements: 6 }]);
+        element.write().unwrap().next = Some(next);
+        assert_eq!(graph.validate(), Ok(()));
+    }
+}
@@ -5,8 +5,16 @@
 
 #![allow(dead_code)] // TODO: remove after dev is done
 
+#[cfg(test)]
+#[macro_use]
+mod test_utils;
+
+#[macro_use]
+mod node_algorithms;
+
 pub mod simple;
 pub mod neural;
+pub mod concurrent;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,288 @@
+use std::{
+    rc::Rc,
+    cell::RefCell,
+    marker::PhantomData
+};
+
+use bionet_common::{
+    sensor::SensorData,
+    data::{ DataDeductor, DataTypeValue }
+};
+
+use super::{
+    element::Element,
+    entry::Entry,
+    graph::ASAGraph
+};
+
+/// Moving past either end parks the cursor on a ghost position where `current` is `None`;
+/// from there `move_next` wraps to the minimum and `move_prev` to the maximum,
+/// as with `std::collections::linked_list::Cursor`.
+pub struct Cursor<'a, Key, const ORDER: usize = 25>
+where Key: SensorData, [(); ORDER + 1]: {
+    pub(crate) graph: &'a ASAGraph<Key, ORDER>,
+    pub(crate) current: Option<Rc<RefCell<Element<Key, ORDER>>>>
+}
+
+/// Same movement rules as `Cursor`, following the element links in constant time.
+/// Counter updates and removals go through the graph so the node aggregates stay in step.
+pub struct CursorMut<'a, Key, const ORDER: usize = 25>
+where Key: SensorData, [(); ORDER + 1]: {
+    pub(crate) graph: &'a mut ASAGraph<Key, ORDER>,
+    pub(crate) current: Option<Rc<RefCell<Element<Key, ORDER>>>>
+}
+
+fn next_element<Key, const ORDER: usize>(
+    graph: &ASAGraph<Key, ORDER>, current: &Option<Rc<RefCell<Element<Key, ORDER>>>>
+) -> Option<Rc<RefCell<Element<Key, ORDER>>>>
+where Key: SensorData, [(); ORDER + 1]: {
+    match current {
+        Some(element) => element.borrow().next.as_ref().and_then(|(next, _)| next.upgrade()),
+        None => graph.element_min.clone()
+    }
+}
+
+fn prev_element<Key, const ORDER: usize>(
+    graph: &ASAGraph<Key, ORDER>, current: &Option<Rc<RefCell<Element<Key, ORDER>>>>
+) -> Option<Rc<RefCell<Element<Key, ORDER>>>>
+where Key: SensorData, [(); ORDER + 1]: {
+    match current {
+        Some(element) => element.borrow().prev.as_ref().and_then(|(prev, _)| prev.upgrade()),
+        None => graph.element_max.clone()
+    }
+}
+
+impl<'a, Key, const ORDER: usize> Cursor<'a, Key, ORDER>
+where
+    Key: SensorData,
+    [(); ORDER + 1]:,
+    PhantomData<Key>: DataDeductor,
+    DataTypeValue: From<Key>
+{
+    pub fn current(&self) -> Option<Rc<RefCell<Element<Key, ORDER>>>> { self.current.clone() }
+
+    pub fn key(&self) -> Option<Key> {
+        self.current.as_ref().map(|element| *dyn_clone::clone_box(&element.borrow().key))
+    }
+
+    pub fn seek(&mut self, key: &Key) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        self.current = self.graph.ceiling(key);
+        self.current()
+    }
+
+    pub fn move_next(&mut self) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        self.current = next_element(self.graph, &self.current);
+        self.current()
+    }
+
+    pub fn move_prev(&mut self) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        self.current = prev_element(self.graph, &self.current);
+        self.current()
+    }
+
+    pub fn peek(&self) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        next_element(self.graph, &self.current)
+    }
+
+    pub fn peek_prev(&self) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        prev_element(self.graph, &self.current)
+    }
+}
+
+impl<'a, Key, const ORDER: usize> CursorMut<'a, Key, ORDER>
+where
+    Key: SensorData,
+    [(); ORDER + 1]:,
+    PhantomData<Key>: DataDeductor,
+    DataTypeValue: From<Key>
+{
+    pub fn current(&self) -> Option<Rc<RefCell<Element<Key, ORDER>>>> { self.current.clone() }
+
+    pub fn key(&self) -> Option<Key> {
+        self.current.as_ref().map(|element| *dyn_clone::clone_box(&element.borrow().key))
+    }
+
+    pub fn as_cursor(&self) -> Cursor<'_, Key, ORDER> {
+        Cursor { graph: self.graph, current: self.current() }
+    }
+
+    pub fn seek(&mut self, key: &Key) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        self.current = self.graph.ceiling(key);
+        self.current()
+    }
+
+    pub fn move_next(&mut self) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        self.current = next_element(self.graph, &self.current);
+        self.current()
+    }
+
+    pub fn move_prev(&mut self) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        self.current = prev_element(self.graph, &self.current);
+        self.current()
+    }
+
+    pub fn peek(&self) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        next_element(self.graph, &self.current)
+    }
+
+    pub fn peek_prev(&self) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        prev_element(self.graph, &self.current)
+    }
+
+    pub fn increment_counter(&mut self) -> Option<usize> {
+        let key = self.key()?;
+        match self.graph.entry(&key) {
+            Entry::Occupied(mut entry) => {
+                entry.add(1, 1.0f32);
+                Some(entry.counter())
+            },
+            Entry::Vacant(_) => None
+        }
+    }
+
+    /// Leaves the cursor on the element that followed the removed one.
+    pub fn remove_current(&mut self) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        let key = self.key()?;
+        let next = self.peek();
+        let element = self.graph.remove_all(&key);
+        self.current = next;
+        element
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::graph::ASAGraph;
+
+    #[test]
+    fn cursor() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in (0..40).step_by(2) { graph.insert(&i); }
+
+        let mut cursor = graph.cursor();
+        assert_eq!(cursor.key(), Some(0));
+        assert!(cursor.move_prev().is_none());
+        assert_eq!(cursor.peek().unwrap().borrow().key, 0);
+        assert_eq!(cursor.move_prev().unwrap().borrow().key, 38);
+        assert!(cursor.move_next().is_none());
+
+        assert_eq!(cursor.seek(&7).unwrap().borrow().key, 8);
+        assert_eq!(cursor.seek(&10).unwrap().borrow().key, 10);
+        assert_eq!(cursor.peek().unwrap().borrow().key, 12);
+        assert_eq!(cursor.peek_prev().unwrap().borrow().key, 8);
+        assert_eq!(cursor.move_next().unwrap().borrow().key, 12);
+        assert_eq!(cursor.move_prev().unwrap().borrow().key, 10);
+        assert!(cursor.seek(&39).is_none());
+        assert_eq!(cursor.peek_prev().unwrap().borrow().key, 38);
+
+        let other = ASAGraph::<i32, 3>::new_bulk("other", &[3, 9, 12, 13, 30, 36, 50]);
+        let mut left = graph.cursor();
+        let mut right = other.cursor();
+        let mut joined = Vec::new();
+        while let (Some(left_key), Some(right_key)) = (left.key(), right.key()) {
+            if left_key < right_key {
+                left.seek(&right_key);
+            } else if left_key > right_key {
+                right.seek(&left_key);
+            } else {
+                joined.push(left_key);
+                left.move_next();
+                right.move_next();
+            }
+        }
+        assert_eq!(joined, vec![12, 30, 36]);
+    }
+
+    #[test]
+    fn cursor_mut() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 0..30 { graph.insert(&i); }
+
+        let mut cursor = graph.cursor_mut();
+        assert_eq!(cursor.seek(&10).unwrap().borrow().key, 10);
+        assert_eq!(cursor.increment_counter(), Some(2));
+        assert_eq!(cursor.increment_counter(), Some(3));
+        assert_eq!(cursor.as_cursor().peek().unwrap().borrow().key, 11);
+
+        while let Some(key) = cursor.key() {
+            if key % 3 == 0 {
+                assert_eq!(cursor.remove_current().unwrap().borrow().key, key);
+            } else {
+                cursor.move_next();
+            }
+        }
+        assert!(cursor.remove_current().is_none());
+        assert!(cursor.increment_counter().is_none());
+        assert_eq!(cursor.move_prev().unwrap().borrow().key, 29);
+        assert_eq!(cursor.remove_current().unwrap().borrow().key, 29);
+        assert!(cursor.current().is_none());
+
+        assert_eq!(graph.count_elements_unique(), 23);
+        assert_eq!(graph.count_elements_agg(), 25);
+        assert_eq!(graph.key_max, Some(28));
+        assert_eq!(graph.search(&10).unwrap().borrow().counter, 3);
+        assert!(graph.search(&12).is_none());
+        assert_eq!(graph.iter().map(|e| e.borrow().key).filter(|k| k % 3 == 0).count(), 4);
+        assert_eq!(graph.rank(&20), 17);
+    }
+
+    #[test]
+    fn cursor_ends() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 0..10 { graph.insert(&i); }
+
+        let mut cursor = graph.cursor();
+        assert!(cursor.seek(&9).is_some());
+        assert!(cursor.move_next().is_none());
+        assert!(cursor.key().is_none());
+        assert_eq!(cursor.peek().unwrap().borrow().key, 0);
+        assert_eq!(cursor.peek_prev().unwrap().borrow().key, 9);
+        assert_eq!(cursor.move_next().unwrap().borrow().key, 0);
+        assert!(cursor.move_prev().is_none());
+        assert_eq!(cursor.move_prev().unwrap().borrow().key, 9);
+
+        let mut cursor = graph.cursor_mut();
+        assert_eq!(cursor.key(), Some(0));
+        assert!(cursor.move_prev().is_none());
+        assert_eq!(cursor.move_prev().unwrap().borrow().key, 9);
+        assert!(cursor.move_next().is_none());
+        assert!(cursor.increment_counter().is_none());
+        assert_eq!(cursor.move_next().unwrap().borrow().key, 0);
+        assert!(cursor.seek(&10).is_none());
+        assert_eq!(cursor.move_prev().unwrap().borrow().key, 9);
+        assert_eq!(cursor.increment_counter(), Some(2));
+        assert_eq!(graph.count_elements_agg(), 11);
+
+        let mut empty = ASAGraph::<i32, 3>::new("empty");
+        let mut cursor = empty.cursor_mut();
+        assert!(cursor.current().is_none());
+        assert!(cursor.move_next().is_none());
+        assert!(cursor.move_prev().is_none());
+        assert!(cursor.seek(&1).is_none());
+    }
+
+    #[test]
+    fn cursor_mut_walk() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 0..200 { graph.insert(&((i * 37) % 200)); }
+        for i in (0..200).step_by(7) { graph.remove_range(i..=i); }
+        let keys: Vec<i32> = graph.iter().map(|e| e.borrow().key).collect();
+
+        let mut cursor = graph.cursor_mut();
+        let mut forward = Vec::new();
+        while let Some(key) = cursor.key() {
+            forward.push(key);
+            cursor.increment_counter();
+            cursor.move_next();
+        }
+        let mut backward = Vec::new();
+        while let Some(element) = cursor.move_prev() { backward.push(element.borrow().key); }
+        backward.reverse();
+        assert_eq!(forward, keys);
+        assert_eq!(backward, keys);
+        assert_eq!(cursor.seek(&14).unwrap().borrow().key, 15);
+
+        assert_eq!(graph.count_elements_agg(), 2 * keys.len());
+        assert_eq!(graph.validate(), Ok(()));
+    }
+}
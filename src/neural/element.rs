@@ -2,7 +2,7 @@ use std::{
     fmt::{ Display, Formatter, Result as FmtResult },
     rc::{ Rc, Weak },
     cell::RefCell,
-    collections::HashMap,
+    collections::{ HashMap, BTreeSet },
     marker::PhantomData
 };
 
@@ -18,16 +18,24 @@ use bionet_common::{
     data::{ DataDeductor, DataCategory, DataTypeValue, DataType }
 };
 
+// The elements on either side of a key, as found by a failed search or left behind by unlink.
+pub(crate) type Neighbours<Key, const ORDER: usize> = (
+    Option<Rc<RefCell<Element<Key, ORDER>>>>, Option<Rc<RefCell<Element<Key, ORDER>>>>
+);
+
 #[derive(Clone)]
 pub struct Element<Key, const ORDER: usize>
 where Key: SensorData, [(); ORDER + 1]: {
     pub key: Key,
-    pub counter: usize,
+    pub(crate) counter: usize,
+    pub(crate) weighted_counter: f32,
+    pub(crate) records: Option<BTreeSet<usize>>,
     pub activation: f32,
     pub parent: Rc<str>,
     pub(crate) self_ptr: Weak<RefCell<Element<Key, ORDER>>>,
     pub next: Option<(Weak<RefCell<Element<Key, ORDER>>>, f32)>,
     pub prev: Option<(Weak<RefCell<Element<Key, ORDER>>>, f32)>,
+    #[allow(clippy::type_complexity)]
     pub definitions: HashMap<ConnectionID, Rc<RefCell<dyn Connection<From = dyn Neuron, To = dyn Neuron>>>>,
     pub(crate) data_type: PhantomData<Key>
 }
@@ -47,6 +55,8 @@ where
                 Element {
                     key: *dyn_clone::clone_box(key),
                     counter: 1,
+                    weighted_counter: 1.0f32,
+                    records: None,
                     activation: 0.0f32,
                     parent: parent.clone(),
                     self_ptr: Weak::new(), 
@@ -62,6 +72,32 @@ where
         element_ptr
     }
 
+    pub fn counter(&self) -> usize { self.counter }
+
+    pub fn weighted_counter(&self) -> f32 { self.weighted_counter }
+
+    pub fn records(&self) -> Option<&BTreeSet<usize>> { self.records.as_ref() }
+
+    // Counter and weight of the occurrences inserted without a record id;
+    // every recorded occurrence counts once with weight 1.
+    pub(crate) fn anonymous_counters(&self) -> (usize, f32) {
+        let recorded = self.records.as_ref().map_or(0, |records| records.len());
+        (self.counter - recorded, self.weighted_counter - recorded as f32)
+    }
+
+    // Adds another element's occurrences; a record held by both is counted once.
+    pub(crate) fn absorb(&mut self, counter: usize, weighted_counter: f32, records: Option<BTreeSet<usize>>) {
+        self.counter += counter;
+        self.weighted_counter += weighted_counter;
+        if let Some(records) = records {
+            let own_records = self.records.get_or_insert_with(BTreeSet::new);
+            let repeated = own_records.intersection(&records).count();
+            own_records.extend(records);
+            self.counter -= repeated;
+            self.weighted_counter -= repeated as f32;
+        }
+    }
+
     pub(crate) fn set_connections(
         element_ptr: &Rc<RefCell<Element<Key, ORDER>>>,
         prev_opt: Option<&Rc<RefCell<Element<Key, ORDER>>>>,
@@ -70,25 +106,51 @@ where
     ) {
         let mut element = element_ptr.borrow_mut();
         
-        if prev_opt.is_some() {
-            let prev_ptr = prev_opt.unwrap();
-            let weight = (&*element).weight(&*prev_ptr.borrow(), range);
+        if let Some(prev_ptr) = prev_opt {
+            let weight = element.weight(&*prev_ptr.borrow(), range);
             element.prev = Some((Rc::downgrade(prev_ptr), weight));
             prev_ptr.borrow_mut().next = Some((Rc::downgrade(element_ptr), weight));
         } else { 
             element.prev = None; 
         }
 
-        if next_opt.is_some() {
-            let next_ptr = next_opt.unwrap();
-            let weight = (&*element).weight(&*next_ptr.borrow(), range);
+        if let Some(next_ptr) = next_opt {
+            let weight = element.weight(&*next_ptr.borrow(), range);
             element.next = Some((Rc::downgrade(next_ptr), weight));
-            next_ptr.borrow_mut().prev = Some((Rc::downgrade(&element_ptr), weight));
+            next_ptr.borrow_mut().prev = Some((Rc::downgrade(element_ptr), weight));
         } else { 
             element.next = None; 
         }
     }
 
+    pub(crate) fn link(
+        prev_ptr: &Rc<RefCell<Element<Key, ORDER>>>,
+        next_ptr: &Rc<RefCell<Element<Key, ORDER>>>,
+        range: f32
+    ) {
+        let weight = prev_ptr.borrow().weight(&*next_ptr.borrow(), range);
+        prev_ptr.borrow_mut().next = Some((Rc::downgrade(next_ptr), weight));
+        next_ptr.borrow_mut().prev = Some((Rc::downgrade(prev_ptr), weight));
+    }
+
+    pub(crate) fn unlink(element_ptr: &Rc<RefCell<Element<Key, ORDER>>>, range: f32) -> Neighbours<Key, ORDER> {
+        let (prev_opt, next_opt) = {
+            let mut element = element_ptr.borrow_mut();
+            let prev_opt = element.prev.take().and_then(|prev| prev.0.upgrade());
+            let next_opt = element.next.take().and_then(|next| next.0.upgrade());
+            (prev_opt, next_opt)
+        };
+
+        match (&prev_opt, &next_opt) {
+            (Some(prev_ptr), Some(next_ptr)) => Self::link(prev_ptr, next_ptr, range),
+            (Some(prev_ptr), None) => prev_ptr.borrow_mut().next = None,
+            (None, Some(next_ptr)) => next_ptr.borrow_mut().prev = None,
+            (None, None) => {}
+        }
+
+        (prev_opt, next_opt)
+    }
+
     pub fn weight(&self, other: &Self, range: f32) -> f32 {
         1.0f32 - (other.key.distance(&self.key) as f32).abs() / range
     }
@@ -114,8 +176,7 @@ where
                     &mut element.borrow()
                         .defined_neurons()
                         .values()
-                        .cloned()
-                        .into_iter().map(
+                        .map(
                             |neuron| (
                                 neuron.clone(), 
                                 element.borrow().activation() / defined_neurons_len as f32
@@ -147,8 +208,7 @@ where
                     &mut element.borrow()
                         .defined_neurons()
                         .values()
-                        .cloned()
-                        .into_iter().map(
+                        .map(
                             |neuron| (
                                 neuron.clone(), 
                                 element.borrow().activation() / defined_neurons_len as f32
@@ -180,14 +240,13 @@ where
         let defined_neurons_len = self.defined_neurons().len();
         self.defined_neurons()
             .values()
-            .cloned()
-            .into_iter().map(|x| (x.clone(), self.activation() / defined_neurons_len as f32))
+            .map(|x| (x.clone(), self.activation() / defined_neurons_len as f32))
             .collect()
     }
 
     pub fn defined_neurons(&self) -> HashMap<NeuronID, Rc<RefCell<dyn Neuron>>> {
         let mut neurons = HashMap::new();
-        for (_id, definition) in &self.definitions {
+        for definition in self.definitions.values() {
             let neuron = definition.borrow().to();
             if !neuron.borrow().is_sensor() {
                 neurons.insert(neuron.borrow().id(), neuron.clone());
@@ -228,10 +287,7 @@ where Key: SensorData, [(); ORDER + 1]:, PhantomData<Key>: DataDeductor, DataTyp
         &mut self, signal: f32, propagate_horizontal: bool, propagate_vertical: bool
     ) -> HashMap<NeuronID, Rc<RefCell<dyn Neuron>>> {
         let data_category: DataCategory = self.data_type.data_category();
-        let is_fuzzy_ok = match data_category {
-            DataCategory::Numerical | DataCategory::Ordinal => true,
-            _ => false
-        };
+        let is_fuzzy_ok = matches!(data_category, DataCategory::Numerical | DataCategory::Ordinal);
         let neurons_activation = if propagate_horizontal && is_fuzzy_ok {
             self.fuzzy_activate(signal)
         } else {
@@ -375,11 +431,12 @@ where
 impl<Key, const ORDER: usize> Display for Element<Key, ORDER> 
 where Key: SensorData, [(); ORDER + 1]: {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "[{}:{}]", &self.key, &self.counter)
+        write!(f, "[{}:{}]", self.key, self.counter)
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::useless_conversion)]
 mod tests {
     use std::{
         rc::Rc,
@@ -455,6 +512,23 @@ mod tests {
         assert!(element_3_ptr.borrow().next.is_none());
     }
 
+    #[test]
+    fn unlink_dropped_neighbour() {
+        let graph = ASAGraph::<i32, 3>::new("test");
+        let element_1_ptr: Rc<RefCell<Element<i32, 3>>> = Element::new(&1, &graph.name);
+        let element_2_ptr: Rc<RefCell<Element<i32, 3>>> = Element::new(&2, &graph.name);
+        let element_3_ptr: Rc<RefCell<Element<i32, 3>>> = Element::new(&3, &graph.name);
+        Element::set_connections(&element_2_ptr, Some(&element_1_ptr), Some(&element_3_ptr), 2f32);
+        drop(element_3_ptr);
+
+        let (prev_opt, next_opt) = Element::unlink(&element_2_ptr, 2f32);
+        assert_eq!(prev_opt.unwrap().borrow().key, 1);
+        assert!(next_opt.is_none());
+        assert!(element_1_ptr.borrow().next.is_none());
+        assert!(element_2_ptr.borrow().prev.is_none());
+        assert!(element_2_ptr.borrow().next.is_none());
+    }
+
     #[test]
     fn parent_name() {
         let graph = Rc::new(RefCell::new(ASAGraph::<i32, 3>::new("test")));
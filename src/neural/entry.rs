@@ -0,0 +1,271 @@
+use std::{
+    rc::Rc,
+    cell::RefCell,
+    marker::PhantomData
+};
+
+use bionet_common::{
+    sensor::SensorData,
+    data::{ DataDeductor, DataTypeValue }
+};
+
+use super::{
+    element::Element,
+    node::Node,
+    graph::ASAGraph
+};
+
+pub enum Entry<'a, Key, const ORDER: usize = 25>
+where Key: SensorData, [(); ORDER + 1]: {
+    Occupied(OccupiedEntry<'a, Key, ORDER>),
+    Vacant(VacantEntry<'a, Key, ORDER>)
+}
+
+pub struct OccupiedEntry<'a, Key, const ORDER: usize = 25>
+where Key: SensorData, [(); ORDER + 1]: {
+    pub(crate) graph: &'a mut ASAGraph<Key, ORDER>,
+    pub(crate) node: Rc<RefCell<Node<Key, ORDER>>>,
+    pub(crate) element: Rc<RefCell<Element<Key, ORDER>>>
+}
+
+pub struct VacantEntry<'a, Key, const ORDER: usize = 25>
+where Key: SensorData, [(); ORDER + 1]: {
+    pub(crate) graph: &'a mut ASAGraph<Key, ORDER>,
+    pub(crate) leaf: Rc<RefCell<Node<Key, ORDER>>>,
+    pub(crate) key: Key
+}
+
+impl<'a, Key, const ORDER: usize> Entry<'a, Key, ORDER>
+where
+    Key: SensorData,
+    [(); ORDER + 1]:,
+    PhantomData<Key>: DataDeductor,
+    DataTypeValue: From<Key>
+{
+    /// Returns `None` when the entry is vacant and `counter` is 0.
+    pub fn or_insert(self, counter: usize) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        match self {
+            Entry::Occupied(entry) => Some(entry.element()),
+            Entry::Vacant(entry) => entry.insert(counter)
+        }
+    }
+
+    /// Setting the counter to 0 removes the element and yields the now vacant entry.
+    /// A counter below the number of records is ignored, as in `set_counter`.
+    pub fn and_modify<F>(self, f: F) -> Self
+    where F: FnOnce(&mut usize) {
+        match self {
+            Entry::Occupied(mut entry) => {
+                let mut counter = entry.counter();
+                f(&mut counter);
+                if counter != 0 {
+                    entry.set_counter(counter);
+                    return Entry::Occupied(entry)
+                }
+                let (graph, element) = entry.remove_element();
+                let key = *dyn_clone::clone_box(&element.borrow().key);
+                graph.entry(&key)
+            },
+            Entry::Vacant(entry) => Entry::Vacant(entry)
+        }
+    }
+}
+
+impl<'a, Key, const ORDER: usize> OccupiedEntry<'a, Key, ORDER>
+where
+    Key: SensorData,
+    [(); ORDER + 1]:,
+    PhantomData<Key>: DataDeductor,
+    DataTypeValue: From<Key>
+{
+    pub fn element(&self) -> Rc<RefCell<Element<Key, ORDER>>> { self.element.clone() }
+
+    pub fn counter(&self) -> usize { self.element.borrow().counter }
+
+    /// Returns the previous counter, or `None` and leaves the element untouched when `counter`
+    /// is 0 or below the number of records, which only `remove_record` takes away.
+    pub fn set_counter(&mut self, counter: usize) -> Option<usize> {
+        let recorded = self.element.borrow().records().map_or(0, |records| records.len());
+        if counter == 0 || counter < recorded { return None }
+        let old_counter = std::mem::replace(&mut self.element.borrow_mut().counter, counter);
+        self.element.borrow_mut().weighted_counter *= counter as f32 / old_counter as f32;
+        Node::update_counts_upwards(&self.node);
+        Some(old_counter)
+    }
+
+    pub(crate) fn add(&mut self, counter: usize, weighted_counter: f32) {
+        {
+            let mut element = self.element.borrow_mut();
+            element.counter += counter;
+            element.weighted_counter += weighted_counter;
+        }
+        Node::update_counts_upwards(&self.node);
+    }
+
+    pub fn remove(self) -> Rc<RefCell<Element<Key, ORDER>>> { self.remove_element().1 }
+
+    fn remove_element(self) -> (&'a mut ASAGraph<Key, ORDER>, Rc<RefCell<Element<Key, ORDER>>>) {
+        let key = *dyn_clone::clone_box(&self.element.borrow().key);
+        let element = self.graph.remove_all(&key).unwrap();
+        (self.graph, element)
+    }
+}
+
+impl<'a, Key, const ORDER: usize> VacantEntry<'a, Key, ORDER>
+where
+    Key: SensorData,
+    [(); ORDER + 1]:,
+    PhantomData<Key>: DataDeductor,
+    DataTypeValue: From<Key>
+{
+    pub fn key(&self) -> &Key { &self.key }
+
+    /// Returns `None` and inserts nothing when `counter` is 0.
+    pub fn insert(self, counter: usize) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        self.insert_weighted(counter, counter as f32)
+    }
+
+    pub fn insert_weighted(
+        self, counter: usize, weighted_counter: f32
+    ) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        if counter == 0 { return None }
+        // Resume from the leaf found by entry(); only climb as far as the splits require.
+        let mut node = self.leaf;
+        let (node, element) = loop {
+            if node.borrow().size < Node::<Key, ORDER>::MAX_KEYS { break self.graph.insert_below(node, &self.key) }
+            let parent = node.borrow().parent.as_ref().and_then(|parent| parent.upgrade());
+            match parent {
+                Some(parent) => node = parent,
+                None => break self.graph.insert_into_node(&self.key)
+            }
+        };
+        if counter != 1 || weighted_counter != 1.0f32 {
+            element.borrow_mut().counter = counter;
+            element.borrow_mut().weighted_counter = weighted_counter;
+            Node::update_counts_upwards(&node);
+        }
+        Some(element)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ super::graph::ASAGraph, Entry };
+
+    #[test]
+    fn entry() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 0..20 { graph.insert(&i); }
+
+        let element = graph.entry(&5).and_modify(|counter| *counter += 1).or_insert(1).unwrap();
+        assert_eq!(element.borrow().counter, 2);
+        let element = graph.entry(&25).and_modify(|counter| *counter += 1).or_insert(7).unwrap();
+        assert_eq!(element.borrow().counter, 7);
+        assert_eq!(graph.search(&25).unwrap().borrow().counter, 7);
+        assert_eq!(graph.count_elements_agg(), 28);
+
+        match graph.entry(&10) {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.counter(), 1);
+                assert_eq!(entry.set_counter(4), Some(1));
+                assert_eq!(entry.element().borrow().key, 10);
+            },
+            Entry::Vacant(_) => panic!("10 should be occupied")
+        }
+        assert_eq!(graph.count_elements_agg(), 31);
+
+        match graph.entry(&-1) {
+            Entry::Vacant(entry) => assert_eq!(*entry.key(), -1),
+            Entry::Occupied(_) => panic!("-1 should be vacant")
+        }
+        assert!(graph.search(&-1).is_none());
+
+        match graph.entry(&3) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove().borrow().key, 3),
+            Entry::Vacant(_) => panic!("3 should be occupied")
+        }
+        assert!(graph.search(&3).is_none());
+        assert_eq!(graph.count_elements_agg(), 30);
+
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in [3, 1, 3, 2, 3] {
+            graph.entry(&i).and_modify(|counter| *counter += 1).or_insert(1);
+        }
+        let items: Vec<(i32, usize)> = graph.iter()
+            .map(|e| (e.borrow().key, e.borrow().counter)).collect();
+        assert_eq!(items, vec![(1, 1), (2, 1), (3, 3)]);
+        assert_eq!(graph.count_elements_agg(), 5);
+    }
+
+    #[test]
+    fn weighted_counters() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 0..20 { graph.insert(&i); }
+        graph.insert_weighted(&7, 0.5f32);
+        graph.insert_weighted(&7, 0.25f32);
+        assert_eq!(graph.search(&7).unwrap().borrow().weighted_counter, 1.75f32);
+
+        match graph.entry(&7) {
+            Entry::Occupied(mut entry) => assert_eq!(entry.set_counter(6), Some(3)),
+            Entry::Vacant(_) => panic!("7 should be occupied")
+        }
+        assert_eq!(graph.search(&7).unwrap().borrow().weighted_counter, 3.5f32);
+        let element = graph.entry(&7).and_modify(|counter| *counter *= 2).or_insert(1).unwrap();
+        assert_eq!((element.borrow().key, element.borrow().counter), (7, 12));
+        assert_eq!(element.borrow().weighted_counter, 7.0f32);
+
+        match graph.entry(&30) {
+            Entry::Vacant(entry) => { entry.insert_weighted(3, 1.5f32); },
+            Entry::Occupied(_) => panic!("30 should be vacant")
+        }
+        assert_eq!(graph.search(&30).unwrap().borrow().counter, 3);
+        assert_eq!(graph.count_elements_agg(), 34);
+        assert_eq!(graph.count_elements_weighted(), 19.0f32 + 7.0f32 + 1.5f32);
+        assert_eq!(graph.validate(), Ok(()));
+    }
+
+    #[test]
+    fn zero_counter() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 0..20 { graph.insert(&i); }
+
+        match graph.entry(&4) {
+            Entry::Occupied(mut entry) => assert_eq!(entry.set_counter(0), None),
+            Entry::Vacant(_) => panic!("4 should be occupied")
+        }
+        assert_eq!(graph.search(&4).unwrap().borrow().counter, 1);
+        assert!(graph.entry(&30).or_insert(0).is_none());
+        match graph.entry(&30) {
+            Entry::Vacant(entry) => assert!(entry.insert_weighted(0, 2.0f32).is_none()),
+            Entry::Occupied(_) => panic!("30 should be vacant")
+        }
+        assert!(graph.search(&30).is_none());
+
+        match graph.entry(&4).and_modify(|counter| *counter -= 1) {
+            Entry::Vacant(entry) => assert_eq!(*entry.key(), 4),
+            Entry::Occupied(_) => panic!("4 should have been removed")
+        }
+        assert!(graph.search(&4).is_none());
+        let element = graph.entry(&4).and_modify(|counter| *counter = 0).or_insert(2).unwrap();
+        assert_eq!(element.borrow().counter, 2);
+        assert_eq!(graph.count_elements_unique(), 20);
+        assert_eq!(graph.count_elements_agg(), 21);
+        assert_eq!(graph.validate(), Ok(()));
+    }
+
+    #[test]
+    fn vacant_insert_from_leaf() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 0..200 {
+            let key = (i * 37) % 200;
+            match graph.entry(&key) {
+                Entry::Vacant(entry) => { entry.insert(1 + key as usize % 3); },
+                Entry::Occupied(_) => panic!("{} should be vacant", key)
+            }
+            assert_eq!(graph.validate(), Ok(()));
+        }
+        let keys: Vec<i32> = graph.iter().map(|e| e.borrow().key).collect();
+        assert_eq!(keys, (0..200).collect::<Vec<_>>());
+        assert_eq!(graph.count_elements_agg(), (0..200).map(|key| 1 + key % 3).sum::<usize>());
+    }
+}
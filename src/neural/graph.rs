@@ -1,10 +1,12 @@
 use std::{
     rc::Rc,
     cell::RefCell,
-    collections::HashMap,
-    cmp::Ordering::*,
+    collections::{ HashMap, BTreeSet, BinaryHeap },
+    cmp::{ Ordering, Ordering::* },
     marker::PhantomData,
-    fmt::{ Display, Formatter, Result as FmtResult }
+    ops::{ RangeBounds, Bound::{ self, * } },
+    fmt::{ Display, Formatter, Result as FmtResult },
+    thread
 };
 
 use bionet_common::{ 
@@ -14,10 +16,15 @@ use bionet_common::{
 };
 
 use super::{
-    element::Element,
-    node::Node
+    element::{ Element, Neighbours },
+    node::Node,
+    entry::{ Entry, OccupiedEntry, VacantEntry },
+    cursor::{ Cursor, CursorMut }
 };
 
+pub use crate::simple::graph::CounterSemantics;
+use crate::simple::graph::subtract_counters;
+
 #[derive(Clone)]
 pub struct ASAGraph<Key, const ORDER: usize = 25>
 where Key: SensorData, [(); ORDER + 1]: {
@@ -72,6 +79,64 @@ where
         graph
     }
 
+    /// Sorts the keys and counts duplicates into single elements before building the tree bottom-up.
+    /// Keys that are not equal to themselves, such as `f64::NAN`, have no place in the order and are skipped.
+    pub fn new_bulk(name: &str, data: &[Key]) -> Self {
+        let mut graph = Self::new(name);
+        let mut keys: Vec<Key> = data.iter()
+            .filter(|key| key.partial_compare(*key) == Some(Equal))
+            .map(|key| *dyn_clone::clone_box(key))
+            .collect();
+        keys.sort_by(|a, b| a.partial_compare(b).unwrap_or(Equal));
+
+        let mut elements: Vec<Rc<RefCell<Element<Key, ORDER>>>> = Vec::new();
+        for key in &keys {
+            let is_duplicate = match elements.last() {
+                Some(last) => last.borrow().key.equals(key),
+                None => false
+            };
+            if is_duplicate {
+                elements.last().unwrap().borrow_mut().counter += 1;
+                elements.last().unwrap().borrow_mut().weighted_counter += 1.0f32;
+            } else {
+                elements.push(Element::new(key, &graph.name));
+            }
+        }
+        graph.rebuild(elements);
+        graph
+    }
+
+    /// Like `new_bulk`, recording each record id once per key. Keys that are not equal to themselves are skipped.
+    pub fn new_bulk_records(name: &str, data: &[(Key, usize)]) -> Self {
+        let mut graph = Self::new(name);
+        let mut pairs: Vec<(Key, usize)> = data.iter()
+            .filter(|(key, _)| key.partial_compare(key) == Some(Equal))
+            .map(|(key, record)| (*dyn_clone::clone_box(key), *record))
+            .collect();
+        pairs.sort_by(|a, b| a.0.partial_compare(&b.0).unwrap_or(Equal).then(a.1.cmp(&b.1)));
+        pairs.dedup_by(|a, b| a.0.equals(&b.0) && a.1 == b.1);
+
+        let mut elements: Vec<Rc<RefCell<Element<Key, ORDER>>>> = Vec::new();
+        for (key, record) in &pairs {
+            let is_duplicate = match elements.last() {
+                Some(last) => last.borrow().key.equals(key),
+                None => false
+            };
+            if is_duplicate {
+                let mut last = elements.last().unwrap().borrow_mut();
+                last.counter += 1;
+                last.weighted_counter += 1.0f32;
+                last.records.as_mut().unwrap().insert(*record);
+            } else {
+                let element = Element::new(key, &graph.name);
+                element.borrow_mut().records = Some(BTreeSet::from([*record]));
+                elements.push(element);
+            }
+        }
+        graph.rebuild(elements);
+        graph
+    }
+
     pub fn new_rc_from_vec(name: &str, data: &[Key]) -> Rc<RefCell<Self>> {
         Rc::new(RefCell::new(Self::new_from_vec(name, data)))
     }
@@ -87,20 +152,28 @@ where
     pub fn data_category(&self) -> DataCategory { self.data_type.data_category() }
 
     pub fn search(&self, key: &Key) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        self.search_neighbours(key).ok()
+    }
+
+    fn search_neighbours(&self, key: &Key) -> Result<Rc<RefCell<Element<Key, ORDER>>>, Neighbours<Key, ORDER>> {
         let node = &self.root;
         
-        let (key_min, key_max) = self.extreme_keys()?;
+        let (key_min, key_max) = match self.extreme_keys() {
+            Some(extreme_keys) => extreme_keys,
+            None => return Err((None, None))
+        };
 
         if key.distance(key_max) > key.distance(key_min) {
-            return Self::search_left(key, &*node.borrow())
+            Self::search_left(key, &*node.borrow())
         } else {
-            return Self::search_right(key, &*node.borrow())
+            Self::search_right(key, &*node.borrow())
         }
     }
 
-    fn search_left<'a, 'b>(
-        key: &'a Key, mut node: &'b Node<Key, ORDER>
-    ) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+    fn search_left(
+        key: &Key, mut node: &Node<Key, ORDER>
+    ) -> Result<Rc<RefCell<Element<Key, ORDER>>>, Neighbours<Key, ORDER>> {
+        let (mut prev, mut next) = (None, None);
         loop {
             let mut index = 0;
             {
@@ -115,10 +188,11 @@ where
 
                 if index < node.size && key.equals(current_key) {
                     let element = node.elements[index].as_ref().unwrap().clone();
-                    return Some(element)
-                } else if node.is_leaf {
-                    return None
+                    return Ok(element)
                 }
+                if index > 0 { prev = node.elements[index - 1].clone(); }
+                if index < node.size { next = node.elements[index].clone(); }
+                if node.is_leaf { return Err((prev, next)) }
             }
                 
             let node_ptr = node.children[index].as_ref().unwrap();
@@ -126,9 +200,10 @@ where
         }
     }
 
-    fn search_right<'a, 'b>(
-        key: &'a Key, mut node: &'b Node<Key, ORDER>
-    ) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+    fn search_right(
+        key: &Key, mut node: &Node<Key, ORDER>
+    ) -> Result<Rc<RefCell<Element<Key, ORDER>>>, Neighbours<Key, ORDER>> {
+        let (mut prev, mut next) = (None, None);
         loop {
             let mut index = node.size - 1;
             {
@@ -141,25 +216,143 @@ where
 
                 if key.equals(current_key) {
                     let element = node.elements[index].as_ref().unwrap().clone();
-                    return Some(element)
-                } else if node.is_leaf {
-                    return None
+                    return Ok(element)
                 } else if key.partial_compare(current_key) == Some(Greater) {
+                    prev = node.elements[index].clone();
                     index += 1;
+                    if index < node.size { next = node.elements[index].clone(); }
+                } else {
+                    next = node.elements[index].clone();
                 }
+                if node.is_leaf { return Err((prev, next)) }
             }
             let node_ptr = node.children[index].as_ref().unwrap();
             unsafe { node = node_ptr.try_borrow_unguarded().unwrap() };
         }
     }
 
+    pub fn floor(&self, key: &Key) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        match self.search_neighbours(key) {
+            Ok(element) => Some(element),
+            Err((prev, _)) => prev
+        }
+    }
+
+    pub fn ceiling(&self, key: &Key) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        match self.search_neighbours(key) {
+            Ok(element) => Some(element),
+            Err((_, next)) => next
+        }
+    }
+
+    pub fn predecessor(&self, key: &Key) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        match self.search_neighbours(key) {
+            Ok(element) => element.borrow().prev.as_ref().and_then(|prev| prev.0.upgrade()),
+            Err((prev, _)) => prev
+        }
+    }
+
+    pub fn successor(&self, key: &Key) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        match self.search_neighbours(key) {
+            Ok(element) => element.borrow().next.as_ref().and_then(|next| next.0.upgrade()),
+            Err((_, next)) => next
+        }
+    }
+
+    pub fn nearest(&self, key: &Key, k: usize) -> Vec<Rc<RefCell<Element<Key, ORDER>>>> {
+        self.expand_by_distance(key, |count, _| count < k)
+    }
+
+    pub fn within(&self, key: &Key, radius: f64) -> Vec<Rc<RefCell<Element<Key, ORDER>>>> {
+        self.expand_by_distance(key, |_, distance| distance <= radius)
+    }
+
+    fn expand_by_distance<F>(&self, key: &Key, mut is_accepted: F) -> Vec<Rc<RefCell<Element<Key, ORDER>>>>
+    where F: FnMut(usize, f64) -> bool {
+        let mut result = Vec::new();
+        let mut left = self.floor(key);
+        let mut right = self.successor(key);
+        loop {
+            let left_distance = left.as_ref().map(|element| key.distance(&element.borrow().key).abs());
+            let right_distance = right.as_ref().map(|element| key.distance(&element.borrow().key).abs());
+            let (is_left, distance) = match (left_distance, right_distance) {
+                (Some(left_distance), Some(right_distance)) if left_distance <= right_distance => (true, left_distance),
+                (Some(_), Some(right_distance)) => (false, right_distance),
+                (Some(left_distance), None) => (true, left_distance),
+                (None, Some(right_distance)) => (false, right_distance),
+                (None, None) => return result
+            };
+            if !is_accepted(result.len(), distance) { return result }
+
+            if is_left {
+                let element = left.take().unwrap();
+                left = element.borrow().prev.as_ref().and_then(|prev| prev.0.upgrade());
+                result.push(element);
+            } else {
+                let element = right.take().unwrap();
+                right = element.borrow().next.as_ref().and_then(|next| next.0.upgrade());
+                result.push(element);
+            }
+        }
+    }
+
+    pub fn iter(&self) -> ASAGraphIntoIterator<'_, Key, ORDER> { self.into_iter() }
+
+    pub fn range<R: RangeBounds<Key>>(&self, range: R) -> ASAGraphRangeIterator<'_, Key, ORDER> {
+        let front = self.lower_bound_element(range.start_bound());
+        let back = self.upper_bound_element(range.end_bound());
+        let is_empty = match (&front, &back) {
+            (Some(front), Some(back)) => {
+                front.borrow().key.partial_compare(&back.borrow().key) == Some(Greater)
+            },
+            _ => true
+        };
+
+        ASAGraphRangeIterator {
+            graph: self,
+            front: if is_empty { None } else { front },
+            back: if is_empty { None } else { back }
+        }
+    }
+
+    fn lower_bound_element(&self, bound: Bound<&Key>) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        match bound {
+            Included(key) => self.ceiling(key),
+            Excluded(key) => self.successor(key),
+            Unbounded => self.element_min.clone()
+        }
+    }
+
+    fn upper_bound_element(&self, bound: Bound<&Key>) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        match bound {
+            Included(key) => self.floor(key),
+            Excluded(key) => self.predecessor(key),
+            Unbounded => self.element_max.clone()
+        }
+    }
+
     pub fn insert(&mut self, key: &Key) -> Rc<RefCell<Element<Key, ORDER>>> {
-        let mut node = self.root.clone();
+        self.insert_into_node(key).1
+    }
 
-        if node.borrow().size == 0 { return self.insert_first_element(&node, key) }
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn insert_into_node(
+        &mut self, key: &Key
+    ) -> (Rc<RefCell<Node<Key, ORDER>>>, Rc<RefCell<Element<Key, ORDER>>>) {
+        let mut node = self.root.clone();
 
         if node.borrow().size == Node::<Key, ORDER>::MAX_KEYS { node = self.split_root(); }
 
+        self.insert_below(node, key)
+    }
+
+    // Descends from a node that is not full, splitting full children on the way down.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn insert_below(
+        &mut self, mut node: Rc<RefCell<Node<Key, ORDER>>>, key: &Key
+    ) -> (Rc<RefCell<Node<Key, ORDER>>>, Rc<RefCell<Element<Key, ORDER>>>) {
+        if node.borrow().size == 0 { return (node.clone(), self.insert_first_element(&node, key)) }
+
         let (key_min, key_max) = self.extreme_keys().unwrap_or_else(|| {
             panic!("element_min / element_min must not be nullptr")
         });
@@ -170,13 +363,16 @@ where
             } else {
                 node.borrow().insert_existing_key(key, false)
             };
-            if let Some(el) = node_insert_result.0 { return el }
+            if let Some(el) = node_insert_result.0 {
+                Node::update_counts_upwards(&node);
+                return (node, el)
+            }
             let mut index = node_insert_result.1;
     
             if node.borrow().is_leaf {
-                let element = Node::insert_key_leaf(&node, key, &self.name, self.range());
+                let element = Node::insert_key_leaf(&node, key, &self.name, self.key_range());
                 self.set_extrema(&element);
-                return element
+                return (node, element)
             } else {
                 let child_size = node.borrow().children[index].as_ref().unwrap().borrow().size;
                 if child_size == Node::<Key, ORDER>::MAX_KEYS {
@@ -184,7 +380,7 @@ where
                     if key.partial_compare(&node.borrow().elements[index].as_ref().unwrap().borrow().key) == Some(Greater) {
                         index += 1 
                     } else if key.equals(node.borrow().keys[index].as_ref().unwrap()) {
-                        return node.borrow().elements[index].as_ref().unwrap().clone()
+                        continue
                     }
                 }
                 let new_node = node.borrow().children[index].as_ref().unwrap().clone();
@@ -193,7 +389,385 @@ where
         }
     }
 
-    pub fn range(&self) -> f32 { 
+    /// Returns `None` when `n` is 0 and `key` is not in the graph.
+    pub fn insert_n(&mut self, key: &Key, n: usize) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        self.insert_counted(key, n, n as f32)
+    }
+
+    pub fn insert_weighted(&mut self, key: &Key, weight: f32) -> Rc<RefCell<Element<Key, ORDER>>> {
+        self.insert_counted(key, 1, weight).unwrap()
+    }
+
+    fn insert_counted(
+        &mut self, key: &Key, counter: usize, weighted_counter: f32
+    ) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => {
+                entry.add(counter, weighted_counter);
+                Some(entry.element())
+            },
+            Entry::Vacant(entry) => entry.insert_weighted(counter, weighted_counter)
+        }
+    }
+
+    /// Each record adds one occurrence with weight 1, on top of the occurrences inserted
+    /// without a record id. Inserting a record the key already holds changes nothing.
+    pub fn insert_record(&mut self, key: &Key, record: usize) -> Rc<RefCell<Element<Key, ORDER>>> {
+        let element = match self.entry(key) {
+            Entry::Occupied(mut entry) => {
+                let is_recorded = entry.element.borrow().records.as_ref()
+                    .is_some_and(|records| records.contains(&record));
+                if is_recorded { return entry.element() }
+                entry.add(1, 1.0f32);
+                entry.element()
+            },
+            Entry::Vacant(entry) => entry.insert(1).unwrap()
+        };
+        element.borrow_mut().records.get_or_insert_with(BTreeSet::new).insert(record);
+        element
+    }
+
+    /// Takes away the one occurrence the record contributed, with weight 1.
+    pub fn remove_record(&mut self, key: &Key, record: usize) -> bool {
+        let is_removed = match self.search(key) {
+            Some(element) => {
+                element.borrow_mut().records.as_mut().is_some_and(|records| records.remove(&record))
+            },
+            None => false
+        };
+        if is_removed { self.remove_occurrence(key, 1.0f32); }
+        is_removed
+    }
+
+    pub fn records(&self, key: &Key) -> BTreeSet<usize> {
+        match self.search(key) {
+            Some(element) => element.borrow().records.clone().unwrap_or_default(),
+            None => BTreeSet::new()
+        }
+    }
+
+    pub fn records_in_range<R: RangeBounds<Key>>(&self, range: R) -> BTreeSet<usize> {
+        let mut records = BTreeSet::new();
+        for element in self.range(range) {
+            records.extend(element.borrow().records.iter().flatten());
+        }
+        records
+    }
+
+    pub fn entry(&mut self, key: &Key) -> Entry<'_, Key, ORDER> {
+        match Node::find_node_or_leaf(&self.root, key) {
+            Ok((node, index)) => {
+                let element = node.borrow().elements[index].as_ref().unwrap().clone();
+                Entry::Occupied(OccupiedEntry { graph: self, node, element })
+            },
+            Err(leaf) => Entry::Vacant(VacantEntry { graph: self, leaf, key: *dyn_clone::clone_box(key) })
+        }
+    }
+
+    pub fn cursor(&self) -> Cursor<'_, Key, ORDER> {
+        Cursor { graph: self, current: self.element_min.clone() }
+    }
+
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, Key, ORDER> {
+        let current = self.element_min.clone();
+        CursorMut { graph: self, current }
+    }
+
+    /// Takes away one occurrence inserted without a record id and returns the remaining counter.
+    /// Returns `None` when the key is missing or all its occurrences are recorded;
+    /// those go through `remove_record`.
+    pub fn remove(&mut self, key: &Key) -> Option<usize> {
+        let (counter, weighted_counter) = self.search(key)?.borrow().anonymous_counters();
+        if counter == 0 { return None }
+        self.remove_occurrence(key, weighted_counter / counter as f32)
+    }
+
+    /// Same as `remove`, subtracting `weight` from the weighted counter.
+    pub fn remove_weighted(&mut self, key: &Key, weight: f32) -> Option<usize> {
+        if self.search(key)?.borrow().anonymous_counters().0 == 0 { return None }
+        self.remove_occurrence(key, weight)
+    }
+
+    fn remove_occurrence(&mut self, key: &Key, weight: f32) -> Option<usize> {
+        match self.entry(key) {
+            Entry::Occupied(entry) if entry.counter() > 1 => {
+                let counter = entry.counter() - 1;
+                entry.element.borrow_mut().counter = counter;
+                entry.element.borrow_mut().weighted_counter -= weight;
+                Node::update_counts_upwards(&entry.node);
+                Some(counter)
+            },
+            Entry::Occupied(entry) => {
+                entry.remove();
+                Some(0)
+            },
+            Entry::Vacant(_) => None
+        }
+    }
+
+    pub fn remove_all(&mut self, key: &Key) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        let element = self.search(key)?;
+
+        Node::remove_key(&self.root, key);
+        self.shrink_root();
+
+        let (prev_opt, next_opt) = Element::unlink(&element, self.key_range());
+        element.borrow_mut().definitions.clear();
+        self.unset_extrema(&element, prev_opt.as_ref(), next_opt.as_ref());
+
+        Some(element)
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        // other may share elements with self through the derived Clone,
+        // so its counters are copied out before any element is borrowed mutably
+        let other_elements: Vec<ElementCounts<Key>> = other.iter()
+            .map(|element| {
+                let element = element.borrow();
+                (
+                    *dyn_clone::clone_box(&element.key),
+                    element.counter,
+                    element.weighted_counter,
+                    element.records.clone()
+                )
+            })
+            .collect();
+        let mut elements = Vec::with_capacity(self.count_elements_unique() + other_elements.len());
+        {
+            let mut self_iter = self.iter().peekable();
+            let mut other_iter = other_elements.into_iter().peekable();
+            loop {
+                let ordering = match (self_iter.peek(), other_iter.peek()) {
+                    (Some(element), Some(other_element)) => {
+                        element.borrow().key.partial_compare(&other_element.0)
+                    },
+                    (Some(_), None) => Some(Less),
+                    (None, Some(_)) => Some(Greater),
+                    (None, None) => break
+                };
+                match ordering {
+                    Some(Less) => elements.push(self_iter.next().unwrap()),
+                    Some(Greater) => {
+                        let (key, counter, weighted_counter, records) = other_iter.next().unwrap();
+                        let element = Element::new(&key, &self.name);
+                        element.borrow_mut().counter = counter;
+                        element.borrow_mut().weighted_counter = weighted_counter;
+                        element.borrow_mut().records = records;
+                        elements.push(element);
+                    },
+                    _ => {
+                        let element = self_iter.next().unwrap();
+                        let (_, counter, weighted_counter, records) = other_iter.next().unwrap();
+                        element.borrow_mut().absorb(counter, weighted_counter, records);
+                        elements.push(element);
+                    }
+                }
+            }
+        }
+        self.rebuild(elements);
+    }
+
+    /// Record ids follow the counters: `Min` keeps the records found in both graphs,
+    /// `Max` and `Sum` keep the records found in either.
+    pub fn intersection(&self, other: &Self, semantics: CounterSemantics) -> Self {
+        self.combine(other, |counter, other_counter| match (counter, other_counter) {
+            (Some(counter), Some(other_counter)) => Some(semantics.apply(counter, other_counter)),
+            _ => None
+        }, |records, other_records| match (records, other_records) {
+            (Some(records), Some(other_records)) if semantics == CounterSemantics::Min => records & other_records,
+            (Some(records), Some(other_records)) => records | other_records,
+            _ => BTreeSet::new()
+        })
+    }
+
+    /// Multiset difference: each key keeps its counter minus the other graph's counter
+    /// and is left out once that reaches zero. Weighted counters shrink in proportion.
+    /// Records found in the other graph are taken away.
+    pub fn difference(&self, other: &Self) -> Self {
+        self.combine(other, |counter, other_counter| match (counter, other_counter) {
+            (Some(counter), None) => Some(counter),
+            (Some(counter), Some(other_counter)) => subtract_counters(counter, other_counter),
+            _ => None
+        }, |records, other_records| match (records, other_records) {
+            (Some(records), Some(other_records)) => records - other_records,
+            (Some(records), None) => records.clone(),
+            _ => BTreeSet::new()
+        })
+    }
+
+    /// Multiset symmetric difference: a key in both graphs keeps the absolute
+    /// difference of its counters, taken from the graph with the larger counter.
+    /// Records found in only one of the graphs are kept.
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        self.combine(other, |counter, other_counter| match (counter, other_counter) {
+            (Some(counter), None) | (None, Some(counter)) => Some(counter),
+            (Some(counter), Some(other_counter)) => subtract_counters(counter, other_counter)
+                .or_else(|| subtract_counters(other_counter, counter)),
+            _ => None
+        }, |records, other_records| match (records, other_records) {
+            (Some(records), Some(other_records)) => records ^ other_records,
+            (Some(records), None) | (None, Some(records)) => records.clone(),
+            _ => BTreeSet::new()
+        })
+    }
+
+    // The counters passed to combine_counters are those of the occurrences inserted
+    // without a record id, and None stands for a key missing from that graph.
+    // Recorded occurrences are combined as sets by combine_records instead.
+    fn combine<F, R>(&self, other: &Self, mut combine_counters: F, mut combine_records: R) -> Self
+    where
+        F: FnMut(Option<(usize, f32)>, Option<(usize, f32)>) -> Option<(usize, f32)>,
+        R: FnMut(Option<&BTreeSet<usize>>, Option<&BTreeSet<usize>>) -> BTreeSet<usize>
+    {
+        let mut graph = Self::new(&self.name);
+        let mut elements = Vec::new();
+        let empty_records = BTreeSet::new();
+        let mut self_iter = self.iter().peekable();
+        let mut other_iter = other.iter().peekable();
+        loop {
+            let ordering = match (self_iter.peek(), other_iter.peek()) {
+                (Some(element), Some(other_element)) => {
+                    element.borrow().key.partial_compare(&other_element.borrow().key)
+                },
+                (Some(_), None) => Some(Less),
+                (None, Some(_)) => Some(Greater),
+                (None, None) => break
+            };
+            let (element, other_element) = match ordering {
+                Some(Less) => (self_iter.next(), None),
+                Some(Greater) => (None, other_iter.next()),
+                _ => (self_iter.next(), other_iter.next())
+            };
+            let (element, other_element) = (
+                element.as_ref().map(|element| element.borrow()),
+                other_element.as_ref().map(|element| element.borrow())
+            );
+            let (element, other_element) = (element.as_deref(), other_element.as_deref());
+            let (counter, weighted_counter) = combine_counters(
+                element.map(Element::anonymous_counters),
+                other_element.map(Element::anonymous_counters)
+            ).unwrap_or((0, 0.0f32));
+            let records = combine_records(
+                element.map(|element| element.records.as_ref().unwrap_or(&empty_records)),
+                other_element.map(|element| element.records.as_ref().unwrap_or(&empty_records))
+            );
+            if counter == 0 && records.is_empty() { continue }
+
+            let key = *dyn_clone::clone_box(&element.or(other_element).unwrap().key);
+            let new_element = Element::new(&key, &graph.name);
+            {
+                let mut new_element = new_element.borrow_mut();
+                new_element.counter = counter + records.len();
+                new_element.weighted_counter = weighted_counter + records.len() as f32;
+                if !records.is_empty() { new_element.records = Some(records); }
+            }
+            elements.push(new_element);
+        }
+        graph.rebuild(elements);
+        graph
+    }
+
+    pub fn split_off(&mut self, key: &Key) -> Self {
+        let mut other = Self::new(&self.name);
+        if self.extreme_keys().is_none() { return other }
+
+        let root = (self.root.clone(), Node::height(&self.root));
+        let (left, right) = Node::split(&root.0, root.1, key, false);
+        self.root = left.0;
+        other.root = right.0;
+
+        let first = match Node::extreme_element(&other.root, false) {
+            Some(element) => element,
+            None => return other
+        };
+        first.borrow_mut().prev = None;
+        other.key_min = Some(*dyn_clone::clone_box(&first.borrow().key));
+        other.element_min = Some(first);
+        other.key_max = self.key_max.take();
+        other.element_max = self.element_max.take();
+
+        match Node::extreme_element(&self.root, true) {
+            Some(last) => {
+                last.borrow_mut().next = None;
+                self.key_max = Some(*dyn_clone::clone_box(&last.borrow().key));
+                self.element_max = Some(last);
+                self.update_elements_weights(self.key_range());
+            },
+            None => {
+                self.key_min = None;
+                self.element_min = None;
+            }
+        }
+        other.update_elements_weights(other.key_range());
+
+        other
+    }
+
+    pub fn remove_range<R: RangeBounds<Key>>(&mut self, range: R) -> usize {
+        if self.extreme_keys().is_none() { return 0 }
+
+        let root = (self.root.clone(), Node::height(&self.root));
+        let (left, rest) = match range.start_bound() {
+            Included(key) => Node::split(&root.0, root.1, key, false),
+            Excluded(key) => Node::split(&root.0, root.1, key, true),
+            Unbounded => (Self::empty_tree(), root)
+        };
+        let (middle, right) = match range.end_bound() {
+            Included(key) => Node::split(&rest.0, rest.1, key, true),
+            Excluded(key) => Node::split(&rest.0, rest.1, key, false),
+            Unbounded => (rest, Self::empty_tree())
+        };
+        self.root = Node::concat(left, right).0;
+
+        let first = match Node::extreme_element(&middle.0, false) {
+            Some(element) => element,
+            None => return 0
+        };
+        let last = Node::extreme_element(&middle.0, true).unwrap();
+        let prev_opt = match first.borrow_mut().prev.take() {
+            Some(prev) => Some(prev.0.upgrade().unwrap()),
+            None => None
+        };
+        let next_opt = match last.borrow_mut().next.take() {
+            Some(next) => Some(next.0.upgrade().unwrap()),
+            None => None
+        };
+
+        let mut counter = 0usize;
+        let mut element = first;
+        loop {
+            counter += element.borrow().counter;
+            element.borrow_mut().definitions.clear();
+            element.borrow_mut().prev = None;
+            let next_element = match element.borrow_mut().next.take() {
+                Some(next) => next.0.upgrade().unwrap(),
+                None => break
+            };
+            element = next_element;
+        }
+
+        match (&prev_opt, &next_opt) {
+            (Some(prev), Some(next)) => Element::link(prev, next, self.key_range()),
+            (Some(prev), None) => prev.borrow_mut().next = None,
+            (None, Some(next)) => next.borrow_mut().prev = None,
+            (None, None) => {}
+        }
+        if prev_opt.is_none() {
+            self.element_min = next_opt.clone();
+            self.key_min = next_opt.as_ref().map(|next| *dyn_clone::clone_box(&next.borrow().key));
+        }
+        if next_opt.is_none() {
+            self.element_max = prev_opt.clone();
+            self.key_max = prev_opt.as_ref().map(|prev| *dyn_clone::clone_box(&prev.borrow().key));
+        }
+        if (prev_opt.is_none() || next_opt.is_none()) && self.extreme_keys().is_some() {
+            self.update_elements_weights(self.key_range());
+        }
+
+        counter
+    }
+
+    pub fn key_range(&self) -> f32 { 
         if self.key_min.is_none() || self.key_max.is_none() { return f32::NAN }
         let ret = self.key_min.as_ref().unwrap().distance(self.key_max.as_ref().unwrap()) as f32;
         if ret == 0.0f32 { 1.0f32 } else { ret }
@@ -213,7 +787,7 @@ where
                 print!("||");
                 for j in 0..(node_size) {
                     let element = node.borrow().elements[j].as_ref().unwrap().clone();
-                    print!("{}:{}|", &element.borrow().key, element.borrow().counter);
+                    print!("{}:{}|", element.borrow().key, element.borrow().counter);
                     if !node.borrow().is_leaf {
                         queue[height + 1].push(node.borrow().children[j].as_ref().unwrap().clone());
                     }
@@ -223,17 +797,17 @@ where
                 }
                 print!("| ");
             }
-            if queue.last().unwrap().len() > 0 {
+            if !queue.last().unwrap().is_empty() {
                 height += 1;
-                println!("");
+                println!();
             } else {
-                println!("");
+                println!();
                 return
             }
         }
     }
 
-    fn extreme_keys<'a>(&'a self) -> Option<(&'a Key, &'a Key)> {
+    fn extreme_keys(&self) -> Option<(&Key, &Key)> {
         if self.key_min.is_none() || self.key_max.is_none() { return None }
         let key_min =  self.key_min.as_ref().unwrap();
         let key_max =  self.key_max.as_ref().unwrap();
@@ -254,6 +828,7 @@ where
         self.element_min = Some(element_pointer.clone());
         self.element_max = Some(element_pointer.clone());
         node.borrow_mut().size = 1;
+        node.borrow_mut().update_counts();
 
         element_pointer
     }
@@ -300,22 +875,81 @@ where
         }
     }
 
+    fn unset_extrema(
+        &mut self,
+        element: &Rc<RefCell<Element<Key, ORDER>>>,
+        prev_opt: Option<&Rc<RefCell<Element<Key, ORDER>>>>,
+        next_opt: Option<&Rc<RefCell<Element<Key, ORDER>>>>
+    ) {
+        let mut should_update_weights = false;
+        if let Some(element_min) = &self.element_min {
+            if Rc::ptr_eq(element_min, element) {
+                self.element_min = next_opt.cloned();
+                self.key_min = next_opt.map(|next| *dyn_clone::clone_box(&next.borrow().key));
+                should_update_weights = true;
+            }
+        }
+        if let Some(element_max) = &self.element_max {
+            if Rc::ptr_eq(element_max, element) {
+                self.element_max = prev_opt.cloned();
+                self.key_max = prev_opt.map(|prev| *dyn_clone::clone_box(&prev.borrow().key));
+                should_update_weights = true;
+            }
+        }
+
+        if should_update_weights && self.extreme_keys().is_some() {
+            self.update_elements_weights(self.key_range());
+        }
+    }
+
+    fn rebuild(&mut self, elements: Vec<Rc<RefCell<Element<Key, ORDER>>>>) {
+        self.root = Node::build(&elements);
+
+        let (first, last) = match (elements.first(), elements.last()) {
+            (Some(first), Some(last)) => (first.clone(), last.clone()),
+            _ => {
+                self.element_min = None;
+                self.element_max = None;
+                self.key_min = None;
+                self.key_max = None;
+                return
+            }
+        };
+        first.borrow_mut().prev = None;
+        last.borrow_mut().next = None;
+        self.key_min = Some(*dyn_clone::clone_box(&first.borrow().key));
+        self.key_max = Some(*dyn_clone::clone_box(&last.borrow().key));
+        self.element_min = Some(first);
+        self.element_max = Some(last);
+
+        let range = self.key_range();
+        for pair in elements.windows(2) { Element::link(&pair[0], &pair[1], range); }
+    }
+
+    fn empty_tree() -> (Rc<RefCell<Node<Key, ORDER>>>, usize) {
+        (Rc::new(RefCell::new(Node::new(true, None))), 0)
+    }
+
+    fn shrink_root(&mut self) {
+        if self.root.borrow().size > 0 || self.root.borrow().is_leaf { return }
+        let new_root = self.root.borrow_mut().children[0].take().unwrap();
+        new_root.borrow_mut().parent = None;
+        self.root = new_root;
+    }
+
     fn update_elements_weights(&mut self, range: f32) {
         let mut element = match &self.element_min {
             Some(e) => e.clone(),
             None => return
         };
         loop {
-            match &element.borrow().prev {
-                Some(e) => {
-                    let element_ptr = element.as_ptr();
-                    let prev_element = e.0.upgrade().unwrap().clone();
-                    let prev_element_ptr = prev_element.as_ptr();
-                    let weight = element.borrow().weight(&*prev_element.borrow(), range);
-                    unsafe { (&mut *element_ptr).prev = Some((Rc::downgrade(&prev_element), weight)) };
-                    unsafe { (&mut *prev_element_ptr).next = Some((Rc::downgrade(&element), weight)) };
-                },
-                None => {}
+            if let Some(e) = &element.borrow().prev {
+                let element_ptr = element.as_ptr();
+                let prev_element = e.0.upgrade().unwrap().clone();
+                let prev_element_ptr = prev_element.as_ptr();
+                let weight = element.borrow().weight(&*prev_element.borrow(), range);
+                unsafe { (&mut *element_ptr).prev = Some((Rc::downgrade(&prev_element), weight)) };
+                unsafe { (&mut *prev_element_ptr).next = Some((Rc::downgrade(&element), weight)) };
             };
 
             let next_element= match &element.borrow().next {
@@ -334,35 +968,251 @@ where
         }
     }
 
-    pub fn count_elements_unique(&self) -> usize {
-        let mut counter = 0usize;
-        let mut element = match &self.element_min {
-            Some(e) => e.clone(),
-            None => return counter
+    pub fn count_elements_unique(&self) -> usize { self.root.borrow().count_unique }
+
+    pub fn count_elements_agg(&self) -> usize { self.root.borrow().count_agg }
+
+    pub fn count_elements_weighted(&self) -> f32 { self.root.borrow().weighted_count_agg }
+
+    pub fn rank(&self, key: &Key) -> usize { self.count_below(key, false).0 }
+
+    pub fn select(&self, index: usize) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        let mut index = index;
+        let mut node = self.root.clone();
+        loop {
+            let child = {
+                let node_ref = node.borrow();
+                let mut child_index = node_ref.size;
+                for i in 0..node_ref.size {
+                    if !node_ref.is_leaf {
+                        let child_count = node_ref.children[i].as_ref().unwrap().borrow().count_unique;
+                        if index < child_count {
+                            child_index = i;
+                            break
+                        }
+                        index -= child_count;
+                    }
+                    if index == 0 { return Some(node_ref.elements[i].as_ref().unwrap().clone()) }
+                    index -= 1;
+                }
+                if node_ref.is_leaf { return None }
+                node_ref.children[child_index].as_ref().unwrap().clone()
+            };
+            node = child;
+        }
+    }
+
+    pub fn count_in_range<R: RangeBounds<Key>>(&self, range: R) -> usize {
+        let lower = match range.start_bound() {
+            Included(key) => self.count_below(key, false).0,
+            Excluded(key) => self.count_below(key, true).0,
+            Unbounded => 0
+        };
+        let upper = match range.end_bound() {
+            Included(key) => self.count_below(key, true).0,
+            Excluded(key) => self.count_below(key, false).0,
+            Unbounded => self.count_elements_unique()
         };
+        upper.saturating_sub(lower)
+    }
+
+    fn count_below(&self, key: &Key, include_key: bool) -> (usize, f32) {
+        let (mut count_unique, mut weighted_count) = (0, 0.0f32);
+        let mut node = self.root.clone();
         loop {
-            counter += 1;
-            let new_element= match &element.borrow().next {
-                Some(e) => e.0.upgrade().unwrap().clone(),
-                None => return counter
+            let child = {
+                let node_ref = node.borrow();
+                let mut index = 0;
+                while index < node_ref.size {
+                    match node_ref.keys[index].as_ref().unwrap().partial_compare(key) {
+                        Some(Less) => {},
+                        Some(Equal) if include_key => {},
+                        _ => break
+                    }
+                    if !node_ref.is_leaf {
+                        let child = node_ref.children[index].as_ref().unwrap().borrow();
+                        count_unique += child.count_unique;
+                        weighted_count += child.weighted_count_agg;
+                    }
+                    count_unique += 1;
+                    weighted_count += node_ref.elements[index].as_ref().unwrap().borrow().weighted_counter;
+                    index += 1;
+                }
+                if node_ref.is_leaf { return (count_unique, weighted_count) }
+                node_ref.children[index].as_ref().unwrap().clone()
             };
-            element = new_element;
+            node = child;
         }
     }
 
-    pub fn count_elements_agg(&self) -> usize {
-        let mut counter = 0usize;
-        let mut element = match &self.element_min {
-            Some(e) => e.clone(),
-            None => return counter
+    /// Share of the weighted counters at or below `key`.
+    pub fn cdf(&self, key: &Key) -> f32 {
+        let weighted_count = self.count_elements_weighted();
+        if weighted_count <= 0.0f32 { return 0.0f32 }
+        self.count_below(key, true).1 / weighted_count
+    }
+
+    pub fn pmf(&self, key: &Key) -> f32 {
+        let weighted_count = self.count_elements_weighted();
+        match self.search(key) {
+            Some(element) => element.borrow().weighted_counter / weighted_count,
+            None => 0.0f32
+        }
+    }
+
+    /// Quantiles treat the weighted counters as frequencies: `p` is mapped to the
+    /// position `p * (count_elements_weighted() - 1)`, which with unit weights is
+    /// the index of an occurrence in sorted order.
+    pub fn quantile(&self, p: f32) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        let position = self.quantile_position(p)?;
+        self.select_weighted(position)
+    }
+
+    pub fn quantile_interpolated(&self, p: f32) -> Option<f64> {
+        let position = self.quantile_position(p)? as f64;
+        let lower = key_to_f64(&self.select_weighted(position.floor() as f32)?.borrow().key)?;
+        let upper = key_to_f64(&self.select_weighted(position.ceil() as f32)?.borrow().key)?;
+        Some(lower + (position - position.floor()) * (upper - lower))
+    }
+
+    fn quantile_position(&self, p: f32) -> Option<f32> {
+        if self.count_elements_unique() == 0 || !(0.0f32..=1.0f32).contains(&p) { return None }
+        Some((p * (self.count_elements_weighted() - 1.0f32)).max(0.0f32))
+    }
+
+    pub fn median(&self) -> Option<Rc<RefCell<Element<Key, ORDER>>>> { self.quantile(0.5f32) }
+
+    /// The mean, moments and mode weigh each key by its weighted counter.
+    pub fn stats(&self) -> ASAGraphStats<Key> {
+        let mut stats = ASAGraphStats {
+            count: self.count_elements_agg(),
+            weighted_count: self.count_elements_weighted(),
+            distinct: self.count_elements_unique(),
+            min: self.key_min.as_ref().map(|key| *dyn_clone::clone_box(key)),
+            max: self.key_max.as_ref().map(|key| *dyn_clone::clone_box(key)),
+            mean: None,
+            variance: None,
+            std: None,
+            skewness: None,
+            mode: None
         };
+
+        let mut mode_counter = 0.0f32;
+        for element in self {
+            let element = element.borrow();
+            if element.weighted_counter > mode_counter {
+                mode_counter = element.weighted_counter;
+                stats.mode = Some(*dyn_clone::clone_box(&element.key));
+            }
+        }
+
+        if stats.count == 0 || !matches!(self.data_category(), DataCategory::Numerical) { return stats }
+
+        let values: Vec<(f64, f64)> = self.iter().filter_map(|element| {
+            let element = element.borrow();
+            Some((key_to_f64(&element.key)?, element.weighted_counter as f64))
+        }).collect();
+        let count = values.iter().map(|(_, counter)| counter).sum::<f64>();
+        let mean = values.iter().map(|(value, counter)| value * counter).sum::<f64>() / count;
+        let (m2, m3) = values.iter().fold((0.0f64, 0.0f64), |(m2, m3), (value, counter)| {
+            let deviation = value - mean;
+            (m2 + counter * deviation.powi(2), m3 + counter * deviation.powi(3))
+        });
+        let variance = m2 / count;
+        let std = variance.sqrt();
+
+        stats.mean = Some(mean);
+        stats.variance = Some(variance);
+        stats.std = Some(std);
+        stats.skewness = if std > 0.0f64 { Some(m3 / count / std.powi(3)) } else { None };
+        stats
+    }
+
+    pub fn histogram_equal_width(&self, bins: usize) -> Option<ASAGraphHistogram> {
+        if bins == 0 { return None }
+        let min = key_to_f64(self.key_min.as_ref()?)?;
+        let max = key_to_f64(self.key_max.as_ref()?)?;
+        let width = (max - min) / bins as f64;
+
+        let edges = (0..=bins)
+            .map(|i| if i == bins { max } else { min + i as f64 * width })
+            .collect();
+        let mut counts = vec![0.0f32; bins];
+        for element in self {
+            let element = element.borrow();
+            let value = key_to_f64(&element.key)?;
+            let index = if width > 0.0f64 {
+                (((value - min) / width) as usize).min(bins - 1)
+            } else {
+                0
+            };
+            counts[index] += element.weighted_counter;
+        }
+
+        Some(ASAGraphHistogram { edges, counts })
+    }
+
+    /// Splits the weighted counters into at most `bins` bins of similar frequency.
+    /// Edges are keys, so when one key's counter spans several quantile boundaries
+    /// they collapse into a single edge and fewer than `bins` bins are returned.
+    pub fn histogram_equal_frequency(&self, bins: usize) -> Option<ASAGraphHistogram> {
+        let weighted_count = self.count_elements_weighted();
+        if bins == 0 || self.count_elements_unique() == 0 { return None }
+
+        let mut edges = vec![self.element_min.clone()?];
+        for i in 1..bins {
+            let element = self.select_weighted(i as f32 * weighted_count / bins as f32)?;
+            if !Rc::ptr_eq(&element, edges.last().unwrap()) { edges.push(element) }
+        }
+        let element_max = self.element_max.clone()?;
+        if edges.len() > 1 && Rc::ptr_eq(&element_max, edges.last().unwrap()) { edges.pop(); }
+        edges.push(element_max);
+
+        let mut counts = Vec::with_capacity(edges.len() - 1);
+        let mut lower = 0.0f32;
+        for i in 1..edges.len() {
+            let upper = if i == edges.len() - 1 {
+                weighted_count
+            } else {
+                self.count_below(&edges[i].borrow().key, false).1
+            };
+            counts.push(upper - lower);
+            lower = upper;
+        }
+
+        let edges = edges.iter()
+            .map(|element| key_to_f64(&element.borrow().key))
+            .collect::<Option<Vec<f64>>>()?;
+        Some(ASAGraphHistogram { edges, counts })
+    }
+
+    // The element whose weighted counter covers `position` in the running total;
+    // positions past the total fall on the last element.
+    fn select_weighted(&self, position: f32) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        let mut position = position;
+        let mut node = self.root.clone();
         loop {
-            counter += element.borrow().counter;
-            let new_element= match &element.borrow().next {
-                Some(e) => e.0.upgrade().unwrap().clone(),
-                None => return counter
+            let child = {
+                let node_ref = node.borrow();
+                let mut child_index = node_ref.size;
+                for i in 0..node_ref.size {
+                    if !node_ref.is_leaf {
+                        let child_weight = node_ref.children[i].as_ref().unwrap().borrow().weighted_count_agg;
+                        if position < child_weight {
+                            child_index = i;
+                            break
+                        }
+                        position -= child_weight;
+                    }
+                    let element = node_ref.elements[i].as_ref().unwrap();
+                    let weighted_counter = element.borrow().weighted_counter;
+                    if position < weighted_counter { return Some(element.clone()) }
+                    position -= weighted_counter;
+                }
+                if node_ref.is_leaf { return self.element_max.clone() }
+                node_ref.children[child_index].as_ref().unwrap().clone()
             };
-            element = new_element;
+            node = child;
         }
     }
 
@@ -385,7 +1235,7 @@ where
                                 "activating missing non-categorical sensory neuron {}, inserting",
                                 key
                             );
-                            self.insert(&key)
+                            self.insert(key)
                         } else {
                             log::error!(
                                 "activating missing non-categorical sensory neuron {} with {}",
@@ -438,6 +1288,137 @@ where
     }
 }
 
+impl<Key, const ORDER: usize> ASAGraph<Key, ORDER>
+where
+    Key: SensorData + Send + Sync,
+    [(); ORDER + 1]:,
+    PhantomData<Key>: DataDeductor,
+    DataTypeValue: From<Key>
+{
+    pub fn new_parallel(name: &str, data: &[Key], threads: usize) -> Self {
+        Self::build_parallel(name, data, threads, Self::new_bulk)
+    }
+
+    pub fn new_parallel_records(name: &str, data: &[(Key, usize)], threads: usize) -> Self {
+        Self::build_parallel(name, data, threads, Self::new_bulk_records)
+    }
+
+    fn build_parallel<T: Sync>(name: &str, data: &[T], threads: usize, build: fn(&str, &[T]) -> Self) -> Self {
+        let chunk_size = data.len().div_ceil(threads.max(1)).max(1);
+        let runs: Vec<Vec<ElementCounts<Key>>> = thread::scope(|scope| {
+            let workers: Vec<_> = data.chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || {
+                    build(name, chunk).iter()
+                        .map(|element| {
+                            let element = element.borrow();
+                            (
+                                *dyn_clone::clone_box(&element.key),
+                                element.counter,
+                                element.weighted_counter,
+                                element.records.clone()
+                            )
+                        })
+                        .collect()
+                }))
+                .collect();
+            workers.into_iter().map(|worker| worker.join().unwrap()).collect()
+        });
+
+        let mut graph = Self::new(name);
+        let mut runs: Vec<_> = runs.into_iter().map(|run| run.into_iter()).collect();
+        let mut heads: BinaryHeap<RunHead<Key>> = runs.iter_mut().enumerate()
+            .filter_map(|(run, elements)| Some(RunHead { element: elements.next()?, run }))
+            .collect();
+        let mut elements: Vec<Rc<RefCell<Element<Key, ORDER>>>> = Vec::new();
+        while let Some(RunHead { element: (key, counter, weighted_counter, records), run }) = heads.pop() {
+            if let Some(element) = runs[run].next() { heads.push(RunHead { element, run }); }
+
+            let is_duplicate = match elements.last() {
+                Some(last) => last.borrow().key.equals(&key),
+                None => false
+            };
+            if is_duplicate {
+                elements.last().unwrap().borrow_mut().absorb(counter, weighted_counter, records);
+            } else {
+                let element = Element::new(&key, &graph.name);
+                element.borrow_mut().counter = counter;
+                element.borrow_mut().weighted_counter = weighted_counter;
+                element.borrow_mut().records = records;
+                elements.push(element);
+            }
+        }
+        graph.rebuild(elements);
+        graph
+    }
+}
+
+pub(crate) fn key_to_f64<Key>(key: &Key) -> Option<f64>
+where Key: SensorData, DataTypeValue: From<Key> {
+    match DataTypeValue::from(*dyn_clone::clone_box(key)) {
+        DataTypeValue::U8(v) => Some(v as f64),
+        DataTypeValue::U16(v) => Some(v as f64),
+        DataTypeValue::U32(v) => Some(v as f64),
+        DataTypeValue::U64(v) => Some(v as f64),
+        DataTypeValue::U128(v) => Some(v as f64),
+        DataTypeValue::USize(v) => Some(v as f64),
+        DataTypeValue::I8(v) => Some(v as f64),
+        DataTypeValue::I16(v) => Some(v as f64),
+        DataTypeValue::I32(v) => Some(v as f64),
+        DataTypeValue::I64(v) => Some(v as f64),
+        DataTypeValue::I128(v) => Some(v as f64),
+        DataTypeValue::ISize(v) => Some(v as f64),
+        DataTypeValue::F32(v) => Some(v as f64),
+        DataTypeValue::F64(v) => Some(v),
+        _ => None
+    }
+}
+
+// Key, counter, weighted counter and records of an element, copied out of its graph.
+pub(crate) type ElementCounts<Key> = (Key, usize, f32, Option<BTreeSet<usize>>);
+
+struct RunHead<Key>
+where Key: SensorData {
+    element: ElementCounts<Key>,
+    run: usize
+}
+
+impl<Key> Ord for RunHead<Key>
+where Key: SensorData {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.element.0.partial_compare(&self.element.0).unwrap_or(Equal).then(other.run.cmp(&self.run))
+    }
+}
+
+impl<Key> PartialOrd for RunHead<Key>
+where Key: SensorData {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl<Key> PartialEq for RunHead<Key>
+where Key: SensorData {
+    fn eq(&self, other: &Self) -> bool { self.cmp(other) == Equal }
+}
+
+impl<Key> Eq for RunHead<Key> where Key: SensorData {}
+
+pub struct ASAGraphStats<Key> {
+    pub count: usize,
+    pub weighted_count: f32,
+    pub distinct: usize,
+    pub min: Option<Key>,
+    pub max: Option<Key>,
+    pub mean: Option<f64>,
+    pub variance: Option<f64>,
+    pub std: Option<f64>,
+    pub skewness: Option<f64>,
+    pub mode: Option<Key>
+}
+
+pub struct ASAGraphHistogram {
+    pub edges: Vec<f64>,
+    pub counts: Vec<f32>
+}
+
 impl<'a, Key, const ORDER: usize> IntoIterator for &'a ASAGraph<Key, ORDER> 
 where Key: SensorData, [(); ORDER + 1]: {
     type Item = Rc<RefCell<Element<Key, ORDER>>>;
@@ -446,10 +1427,8 @@ where Key: SensorData, [(); ORDER + 1]: {
     fn into_iter(self) -> Self::IntoIter {
         ASAGraphIntoIterator {
             graph: self,
-            index: match self.element_min.clone() {
-                Some(element_min) => Some(element_min.clone()),
-                None => None
-            },
+            front: self.element_min.clone(),
+            back: self.element_max.clone()
         }
     }
 }
@@ -457,32 +1436,88 @@ where Key: SensorData, [(); ORDER + 1]: {
 pub struct ASAGraphIntoIterator<'a, Key, const ORDER: usize = 25>
 where Key: SensorData, [(); ORDER + 1]: {
     graph: &'a ASAGraph<Key, ORDER>,
-    index: Option<Rc<RefCell<Element<Key, ORDER>>>>
+    front: Option<Rc<RefCell<Element<Key, ORDER>>>>,
+    back: Option<Rc<RefCell<Element<Key, ORDER>>>>
 }
 
 impl<'a, Key, const ORDER: usize> Iterator for ASAGraphIntoIterator<'a, Key, ORDER> 
 where Key: SensorData, [(); ORDER + 1]: {
     type Item = Rc<RefCell<Element<Key, ORDER>>>;
     fn next(&mut self) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
-        let next_option;
-        let result = match self.index.clone() {
-            Some(element) => {
-                next_option = match &element.borrow().next {
-                    Some(next_tuple) => match next_tuple.0.upgrade() {
-                        Some(next) => Some(next),
-                        None => None
-                    },
-                    None => None
-                };
+        let element = self.front.take()?;
 
-                Some(element)
-            },
-            None => return None
-        };
+        if Rc::ptr_eq(&element, self.back.as_ref().unwrap()) {
+            self.back = None;
+        } else {
+            self.front = match &element.borrow().next {
+                Some(next_tuple) => next_tuple.0.upgrade(),
+                None => None
+            };
+        }
 
-        self.index = next_option;
+        Some(element)
+    }
+}
 
-        result
+impl<'a, Key, const ORDER: usize> DoubleEndedIterator for ASAGraphIntoIterator<'a, Key, ORDER> 
+where Key: SensorData, [(); ORDER + 1]: {
+    fn next_back(&mut self) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        let element = self.back.take()?;
+
+        if Rc::ptr_eq(&element, self.front.as_ref().unwrap()) {
+            self.front = None;
+        } else {
+            self.back = match &element.borrow().prev {
+                Some(prev_tuple) => prev_tuple.0.upgrade(),
+                None => None
+            };
+        }
+
+        Some(element)
+    }
+}
+
+pub struct ASAGraphRangeIterator<'a, Key, const ORDER: usize = 25>
+where Key: SensorData, [(); ORDER + 1]: {
+    graph: &'a ASAGraph<Key, ORDER>,
+    front: Option<Rc<RefCell<Element<Key, ORDER>>>>,
+    back: Option<Rc<RefCell<Element<Key, ORDER>>>>
+}
+
+impl<'a, Key, const ORDER: usize> Iterator for ASAGraphRangeIterator<'a, Key, ORDER> 
+where Key: SensorData, [(); ORDER + 1]: {
+    type Item = Rc<RefCell<Element<Key, ORDER>>>;
+    fn next(&mut self) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        let element = self.front.take()?;
+
+        if Rc::ptr_eq(&element, self.back.as_ref().unwrap()) {
+            self.back = None;
+        } else {
+            self.front = match &element.borrow().next {
+                Some(next_tuple) => next_tuple.0.upgrade(),
+                None => None
+            };
+        }
+
+        Some(element)
+    }
+}
+
+impl<'a, Key, const ORDER: usize> DoubleEndedIterator for ASAGraphRangeIterator<'a, Key, ORDER> 
+where Key: SensorData, [(); ORDER + 1]: {
+    fn next_back(&mut self) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+        let element = self.back.take()?;
+
+        if Rc::ptr_eq(&element, self.front.as_ref().unwrap()) {
+            self.front = None;
+        } else {
+            self.back = match &element.borrow().prev {
+                Some(prev_tuple) => prev_tuple.0.upgrade(),
+                None => None
+            };
+        }
+
+        Some(element)
     }
 }
 
@@ -502,7 +1537,7 @@ where Key: SensorData, [(); ORDER + 1]: {
                 write!(f, "||")?;
                 for j in 0..(node_size) {
                     let element = node.borrow().elements[j].as_ref().unwrap().clone();
-                    write!(f, "{}:{}|", &element.borrow().key, element.borrow().counter)?;
+                    write!(f, "{}:{}|", element.borrow().key, element.borrow().counter)?;
                     if !node.borrow().is_leaf {
                         queue[height + 1].push(node.borrow().children[j].as_ref().unwrap().clone());
                     }
@@ -512,11 +1547,11 @@ where Key: SensorData, [(); ORDER + 1]: {
                 }
                 write!(f, "| ")?;
             }
-            if queue.last().unwrap().len() > 0 {
+            if !queue.last().unwrap().is_empty() {
                 height += 1;
-                writeln!(f, "")?;
+                writeln!(f)?;
             } else {
-                writeln!(f, "")?;
+                writeln!(f)?;
                 return Ok(())
             }
         }
@@ -524,11 +1559,14 @@ where Key: SensorData, [(); ORDER + 1]: {
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_vec)]
 pub mod tests {
-    use rand::Rng;
-    use std::{ time::Instant };
+    use rand::{ Rng, SeedableRng, rngs::StdRng };
+    use std::{ collections::BTreeMap, time::Instant, rc::Rc, ops::Bound::Excluded };
 
-    use super::ASAGraph;
+    use bionet_common::{ neuron::NeuronConnect, connection::ConnectionKind };
+
+    use super::{ ASAGraph, CounterSemantics, super::{ entry::Entry, node::Node } };
 
     #[test]
     fn create_empty_graph() {
@@ -730,4 +1768,483 @@ pub mod tests {
 
         println!("{graph}");
     }
+
+    #[test]
+    fn remove() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 1..=9 { graph.insert(&i); }
+        graph.insert(&5);
+
+        assert_eq!(graph.remove(&5), Some(1));
+        assert_eq!(graph.search(&5).unwrap().borrow().counter, 1);
+        assert_eq!(graph.remove(&5), Some(0));
+        assert!(graph.search(&5).is_none());
+        assert_eq!(graph.remove(&5), None);
+        assert_eq!(graph.remove(&10), None);
+
+        assert_eq!(graph.count_elements_unique(), 8);
+        assert_eq!(graph.count_elements_agg(), 8);
+        let four = graph.search(&4).unwrap();
+        let six = graph.search(&6).unwrap();
+        assert!(Rc::ptr_eq(&four.borrow().next.as_ref().unwrap().0.upgrade().unwrap(), &six));
+        assert!(Rc::ptr_eq(&six.borrow().prev.as_ref().unwrap().0.upgrade().unwrap(), &four));
+        assert_eq!(four.borrow().next.as_ref().unwrap().1, 0.75f32);
+    }
+
+    #[test]
+    fn remove_all_extrema() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 1..=9 { graph.insert(&i); }
+
+        let element = graph.remove_all(&1).unwrap();
+        assert_eq!(element.borrow().key, 1);
+        assert!(element.borrow().prev.is_none() && element.borrow().next.is_none());
+        assert_eq!(graph.key_min.unwrap(), 2);
+        assert_eq!(graph.element_min.as_ref().unwrap().borrow().key, 2);
+        assert!(graph.element_min.as_ref().unwrap().borrow().prev.is_none());
+
+        graph.remove_all(&9);
+        assert_eq!(graph.key_max.unwrap(), 8);
+        assert_eq!(graph.element_max.as_ref().unwrap().borrow().key, 8);
+        assert!(graph.element_max.as_ref().unwrap().borrow().next.is_none());
+        for element in graph.into_iter() {
+            if let Some(next) = &element.borrow().next {
+                assert_eq!(next.1, 1.0f32 - 1.0f32 / 6.0f32);
+            }
+        }
+
+        for i in 2..=8 { assert!(graph.remove_all(&i).is_some()); }
+        assert!(graph.key_min.is_none() && graph.key_max.is_none());
+        assert!(graph.element_min.is_none() && graph.element_max.is_none());
+        assert_eq!(graph.count_elements_unique(), 0);
+        assert!(graph.search(&5).is_none());
+
+        graph.insert(&3);
+        assert_eq!(graph.count_elements_unique(), 1);
+        assert_eq!(graph.key_min.unwrap(), 3);
+    }
+
+    #[test]
+    fn remove_all_definitions() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 1..=9 { graph.insert(&i); }
+
+        let five = graph.search(&5).unwrap();
+        let six = graph.search(&6).unwrap();
+        five.borrow_mut().connect_to(six, ConnectionKind::Defining).unwrap();
+        assert_eq!(five.borrow().definitions.len(), 1);
+
+        let removed = graph.remove_all(&5).unwrap();
+        assert!(Rc::ptr_eq(&removed, &five));
+        assert!(removed.borrow().definitions.is_empty());
+    }
+
+    #[test]
+    fn remove_all_3_degree() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 1..=500 { graph.insert(&i); }
+        for i in (1..=500).step_by(3) { graph.insert(&i); }
+
+        for i in (2..=500).step_by(2) { assert!(graph.remove_all(&i).is_some()); }
+        for i in (1..=500).rev().step_by(5) { graph.remove_all(&i); }
+
+        let expected: Vec<i32> = (1..=500)
+            .filter(|i| i % 2 == 1 && (500 - i) % 5 != 0)
+            .collect();
+        let keys: Vec<i32> = graph.into_iter().map(|e| e.borrow().key).collect();
+        assert_eq!(keys, expected);
+        for i in 1..=500 {
+            assert_eq!(graph.search(&i).is_some(), expected.contains(&i));
+        }
+        assert_eq!(graph.key_min.unwrap(), *expected.first().unwrap());
+        assert_eq!(graph.key_max.unwrap(), *expected.last().unwrap());
+    }
+
+    #[test]
+    fn remove_all_25_degree() {
+        let mut graph = ASAGraph::<i32, 25>::new("test");
+        for i in (1..=1000).rev() { graph.insert(&i); }
+
+        for i in 1..=1000 {
+            if i % 7 != 0 { assert!(graph.remove_all(&i).is_some()); }
+        }
+
+        let expected: Vec<i32> = (1..=1000).filter(|i| i % 7 == 0).collect();
+        let keys: Vec<i32> = graph.into_iter().map(|e| e.borrow().key).collect();
+        assert_eq!(keys, expected);
+        for i in &expected { assert!(graph.search(i).is_some()); }
+        assert_eq!(graph.count_elements_unique(), expected.len());
+    }
+
+    #[test]
+    fn remove_range_weights() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 1..=100 { graph.insert(&i); }
+        for i in 10..=14 { graph.insert(&i); }
+
+        assert_eq!(graph.remove_range(10..=19), 15);
+        let nine = graph.search(&9).unwrap();
+        let twenty = graph.search(&20).unwrap();
+        let (next, weight) = nine.borrow().next.clone().unwrap();
+        assert!(Rc::ptr_eq(&next.upgrade().unwrap(), &twenty));
+        assert_eq!(weight, 1.0f32 - 11.0f32 / 99.0f32);
+
+        assert_eq!(graph.remove_range(..5), 4);
+        assert_eq!(graph.remove_range(90..), 11);
+        assert_eq!(nine.borrow().next.as_ref().unwrap().1, 1.0f32 - 11.0f32 / 84.0f32);
+    }
+
+    #[test]
+    fn remove_range_25_degree() {
+        let mut graph = ASAGraph::<i32, 25>::new("test");
+        for i in (1..=2000).rev() { graph.insert(&i); }
+
+        assert_eq!(graph.remove_range(100..1900), 1800);
+        assert_eq!(graph.remove_range(50..=150), 50);
+        let keys: Vec<i32> = graph.into_iter().map(|e| e.borrow().key).collect();
+        let expected: Vec<i32> = (1..50).chain(1900..=2000).collect();
+        assert_eq!(keys, expected);
+        for i in 1..=2000 { assert_eq!(graph.search(&i).is_some(), expected.contains(&i)); }
+    }
+
+    #[test]
+    fn distribution() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        assert_eq!(graph.cdf(&1), 0.0f32);
+        assert!(graph.quantile(0.5f32).is_none() && graph.median().is_none());
+        for i in 1..=10 { graph.insert(&i); }
+        for _ in 0..9 { graph.insert(&10); }
+        for _ in 0..4 { graph.insert(&1); }
+
+        assert_eq!(graph.count_elements_agg(), 23);
+        assert_eq!(graph.cdf(&0), 0.0f32);
+        assert_eq!(graph.cdf(&1), 5.0f32 / 23.0f32);
+        assert_eq!(graph.cdf(&9), 13.0f32 / 23.0f32);
+        assert_eq!(graph.cdf(&100), 1.0f32);
+        assert_eq!(graph.pmf(&10), 10.0f32 / 23.0f32);
+        assert_eq!(graph.pmf(&5), 1.0f32 / 23.0f32);
+        assert_eq!(graph.pmf(&11), 0.0f32);
+
+        assert_eq!(graph.quantile(0.0f32).unwrap().borrow().key, 1);
+        assert_eq!(graph.quantile(0.2f32).unwrap().borrow().key, 1);
+        assert_eq!(graph.quantile(0.25f32).unwrap().borrow().key, 2);
+        assert_eq!(graph.quantile(1.0f32).unwrap().borrow().key, 10);
+        assert!(graph.quantile(1.5f32).is_none());
+        assert_eq!(graph.median().unwrap().borrow().key, 8);
+
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in [1, 2, 3, 4] { graph.insert(&i); }
+        assert_eq!(graph.median().unwrap().borrow().key, 2);
+        assert_eq!(graph.quantile_interpolated(0.5f32), Some(2.5f64));
+        assert_eq!(graph.quantile_interpolated(0.0f32), Some(1.0f64));
+        assert_eq!(graph.quantile_interpolated(1.0f32), Some(4.0f64));
+
+        let graph = ASAGraph::<_, 3>::new_from_vec("test", &["a".to_string(), "b".to_string()]);
+        assert_eq!(graph.quantile(1.0f32).unwrap().borrow().key, "b".to_string());
+        assert!(graph.quantile_interpolated(0.5f32).is_none());
+    }
+
+    #[test]
+    fn stats() {
+        let graph = ASAGraph::<i32, 3>::new("test");
+        let stats = graph.stats();
+        assert_eq!(stats.count, 0);
+        assert!(stats.min.is_none() && stats.mean.is_none() && stats.mode.is_none());
+
+        let graph = ASAGraph::<i32, 3>::new_from_vec("test", &[2, 4, 4, 4, 5, 5, 7, 9]);
+        let stats = graph.stats();
+        assert_eq!(stats.count, 8);
+        assert_eq!(stats.distinct, 5);
+        assert_eq!(stats.min, Some(2));
+        assert_eq!(stats.max, Some(9));
+        assert_eq!(stats.mean, Some(5.0f64));
+        assert_eq!(stats.variance, Some(4.0f64));
+        assert_eq!(stats.std, Some(2.0f64));
+        assert!((stats.skewness.unwrap() - 0.65625f64).abs() < 1e-9);
+        assert_eq!(stats.mode, Some(4));
+
+        let graph = ASAGraph::<i32, 3>::new_from_vec("test", &[3, 3]);
+        assert_eq!(graph.stats().variance, Some(0.0f64));
+        assert!(graph.stats().skewness.is_none());
+
+        let data = ["a", "b", "b", "c"].map(|key| key.to_string());
+        let graph = ASAGraph::<_, 3>::new_from_vec("test", &data);
+        let stats = graph.stats();
+        assert_eq!(stats.count, 4);
+        assert_eq!(stats.distinct, 3);
+        assert_eq!(stats.mode, Some("b".to_string()));
+        assert!(stats.mean.is_none() && stats.std.is_none());
+    }
+
+    #[test]
+    fn histograms() {
+        let graph = ASAGraph::<i32, 3>::new("test");
+        assert!(graph.histogram_equal_width(4).is_none());
+        assert!(graph.histogram_equal_frequency(4).is_none());
+
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 0..=10 { graph.insert(&i); }
+        for _ in 0..5 { graph.insert(&10); }
+
+        let histogram = graph.histogram_equal_width(4).unwrap();
+        assert_eq!(histogram.edges, vec![0.0f64, 2.5f64, 5.0f64, 7.5f64, 10.0f64]);
+        assert_eq!(histogram.counts, vec![3.0f32, 2.0f32, 3.0f32, 8.0f32]);
+        assert!(graph.histogram_equal_width(0).is_none());
+
+        let histogram = graph.histogram_equal_frequency(2).unwrap();
+        assert_eq!(histogram.edges, vec![0.0f64, 8.0f64, 10.0f64]);
+        assert_eq!(histogram.counts, vec![8.0f32, 8.0f32]);
+        let histogram = graph.histogram_equal_frequency(4).unwrap();
+        assert_eq!(histogram.edges, vec![0.0f64, 4.0f64, 8.0f64, 10.0f64]);
+        assert_eq!(histogram.counts, vec![4.0f32, 4.0f32, 8.0f32]);
+        assert_eq!(histogram.counts.iter().sum::<f32>(), graph.count_elements_weighted());
+
+        let graph = ASAGraph::<i32, 3>::new_from_vec("test", &[7, 7, 7]);
+        let histogram = graph.histogram_equal_width(3).unwrap();
+        assert_eq!(histogram.counts, vec![3.0f32, 0.0f32, 0.0f32]);
+        let histogram = graph.histogram_equal_frequency(3).unwrap();
+        assert_eq!(histogram.edges, vec![7.0f64, 7.0f64]);
+        assert_eq!(histogram.counts, vec![3.0f32]);
+
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 0..=3 { graph.insert(&i); }
+        graph.insert_n(&1, 99);
+        let histogram = graph.histogram_equal_frequency(4).unwrap();
+        assert_eq!(histogram.edges, vec![0.0f64, 1.0f64, 3.0f64]);
+        assert_eq!(histogram.counts, vec![1.0f32, 102.0f32]);
+        let histogram = graph.histogram_equal_frequency(100).unwrap();
+        assert_eq!(histogram.edges, vec![0.0f64, 1.0f64, 2.0f64, 3.0f64]);
+        assert_eq!(histogram.counts, vec![1.0f32, 100.0f32, 2.0f32]);
+    }
+
+    #[test]
+    fn weighted_distribution() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        graph.insert_weighted(&1, 0.5f32);
+        graph.insert_weighted(&2, 0.25f32);
+        graph.insert_weighted(&4, 2.0f32);
+        graph.insert_weighted(&4, 0.25f32);
+        assert_eq!(graph.count_elements_agg(), 4);
+        assert_eq!(graph.count_elements_weighted(), 3.0f32);
+
+        let stats = graph.stats();
+        assert_eq!(stats.count, 4);
+        assert_eq!(stats.weighted_count, 3.0f32);
+        assert_eq!(stats.mean, Some(10.0f64 / 3.0f64));
+        assert!((stats.variance.unwrap() - 25.0f64 / 18.0f64).abs() < 1e-9);
+        assert_eq!(stats.mode, Some(4));
+
+        assert_eq!(graph.cdf(&1), 0.5f32 / 3.0f32);
+        assert_eq!(graph.cdf(&3), 0.25f32);
+        assert_eq!(graph.pmf(&4), 0.75f32);
+        assert_eq!(graph.quantile(0.0f32).unwrap().borrow().key, 1);
+        assert_eq!(graph.quantile(0.25f32).unwrap().borrow().key, 2);
+        assert_eq!(graph.quantile(0.5f32).unwrap().borrow().key, 4);
+        assert_eq!(graph.median().unwrap().borrow().key, 4);
+        assert_eq!(graph.quantile_interpolated(0.0f32), Some(1.0f64));
+        assert_eq!(graph.quantile_interpolated(0.25f32), Some(2.5f64));
+
+        let histogram = graph.histogram_equal_width(3).unwrap();
+        assert_eq!(histogram.counts, vec![0.5f32, 0.25f32, 2.25f32]);
+        let histogram = graph.histogram_equal_frequency(2).unwrap();
+        assert_eq!(histogram.edges, vec![1.0f64, 4.0f64]);
+        assert_eq!(histogram.counts, vec![3.0f32]);
+    }
+
+    #[test]
+    fn new_parallel() {
+        let graph = ASAGraph::<i32, 3>::new_parallel("test", &[], 4);
+        assert_eq!(graph.count_elements_unique(), 0);
+        assert!(graph.element_min.is_none());
+
+        let mut rng = StdRng::seed_from_u64(25);
+        for (n, threads) in [(1, 4), (10, 3), (100, 1), (1000, 0), (5000, 8)] {
+            let data: Vec<i32> = (0..n).map(|_| rng.gen_range(-500..500)).collect();
+            let parallel = ASAGraph::<i32, 4>::new_parallel("test", &data, threads);
+            let bulk = ASAGraph::<i32, 4>::new_bulk("test", &data);
+
+            assert!(parallel.validate().is_ok());
+            assert_eq!(parallel.count_elements_agg(), data.len());
+            assert_eq!(parallel.count_elements_unique(), bulk.count_elements_unique());
+            assert_eq!(parallel.key_min, bulk.key_min);
+            assert_eq!(parallel.key_max, bulk.key_max);
+            let items = |graph: &ASAGraph<i32, 4>| graph.iter()
+                .map(|e| {
+                    let e = e.borrow();
+                    (e.key, e.counter, e.weighted_counter, e.next.as_ref().map(|next| next.1))
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(items(&parallel), items(&bulk));
+        }
+    }
+
+    #[test]
+    fn new_parallel_chunk_boundaries() {
+        let sorted: Vec<i32> = (0..100).map(|i| i / 7).collect();
+        let repeated: Vec<i32> = [5; 10].into_iter().chain([3; 7]).chain(0..20).chain([5; 4]).collect();
+        for data in [sorted, repeated] {
+            let bulk = ASAGraph::<i32, 3>::new_bulk("test", &data);
+            for threads in [2, 4, 6, 16] {
+                let parallel = ASAGraph::<i32, 3>::new_parallel("test", &data, threads);
+                assert_eq!(parallel.validate(), Ok(()));
+                let items = |graph: &ASAGraph<i32, 3>| graph.iter()
+                    .map(|e| (e.borrow().key, e.borrow().counter, e.borrow().weighted_counter))
+                    .collect::<Vec<_>>();
+                assert_eq!(items(&parallel), items(&bulk), "{} threads", threads);
+            }
+        }
+    }
+
+    #[test]
+    fn remove_weighted() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 0..20 { graph.insert(&i); }
+        graph.insert_weighted(&7, 0.25f32);
+        graph.insert_weighted(&7, 1.75f32);
+        graph.insert_weighted(&-3, 2.5f32);
+
+        assert_eq!(graph.remove_weighted(&7, 0.25f32), Some(2));
+        assert_eq!(graph.search(&7).unwrap().borrow().weighted_counter, 2.75f32);
+        assert_eq!(graph.remove(&7), Some(1));
+        assert_eq!(graph.search(&7).unwrap().borrow().weighted_counter, 1.375f32);
+        assert_eq!(graph.remove_weighted(&-3, 2.5f32), Some(0));
+        assert!(graph.search(&-3).is_none());
+        assert_eq!(graph.remove_weighted(&-3, 1.0f32), None);
+        assert_eq!(graph.count_elements_agg(), 20);
+        assert_eq!(graph.count_elements_weighted(), 20.375f32);
+        assert_eq!(graph.validate(), Ok(()));
+    }
+
+    #[test]
+    fn records() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for record in 0..60 { graph.insert_record(&(record as i32 % 20), record); }
+        graph.insert(&100);
+
+        assert_eq!(graph.count_elements_agg(), 61);
+        assert_eq!(graph.search(&5).unwrap().borrow().counter, 3);
+        assert_eq!(graph.records(&5).into_iter().collect::<Vec<_>>(), vec![5, 25, 45]);
+        assert!(graph.records(&100).is_empty());
+        assert!(graph.records(&50).is_empty());
+
+        assert_eq!(graph.insert_record(&5, 25).borrow().counter, 3);
+        assert_eq!(graph.count_elements_agg(), 61);
+
+        assert_eq!(
+            graph.records_in_range(3..5).into_iter().collect::<Vec<_>>(),
+            vec![3, 4, 23, 24, 43, 44]
+        );
+        assert_eq!(graph.records_in_range(18..).len(), 6);
+        assert!(graph.records_in_range(21..30).is_empty());
+
+        assert!(graph.remove_record(&5, 25));
+        assert!(!graph.remove_record(&5, 25));
+        assert!(!graph.remove_record(&50, 1));
+        assert_eq!(graph.search(&5).unwrap().borrow().counter, 2);
+        assert_eq!(graph.count_elements_agg(), 60);
+        assert!(graph.remove_record(&5, 5));
+        assert!(graph.remove_record(&5, 45));
+        assert!(graph.search(&5).is_none());
+        assert_eq!(graph.records_in_range(4..=6).len(), 6);
+
+        let mut other = ASAGraph::<i32, 3>::new("other");
+        other.insert_record(&4, 100);
+        other.insert_record(&200, 101);
+        let intersection = graph.intersection(&other, CounterSemantics::Sum);
+        assert_eq!(intersection.records(&4).into_iter().collect::<Vec<_>>(), vec![4, 24, 44, 100]);
+        graph.merge(&other);
+        assert_eq!(graph.records_in_range(4..).len(), 47);
+        assert_eq!(graph.records(&200).into_iter().collect::<Vec<_>>(), vec![101]);
+        let rest = graph.split_off(&10);
+        assert_eq!(rest.records_in_range(..).len(), 31);
+        assert!(graph.search(&100).is_none() && rest.search(&100).unwrap().borrow().records().is_none());
+    }
+
+    #[test]
+    fn records_with_plain_occurrences() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 0..20 { graph.insert(&i); }
+        graph.insert_weighted(&5, 0.5f32);
+        graph.insert_record(&5, 10);
+        graph.insert_record(&5, 11);
+        assert_eq!(graph.search(&5).unwrap().borrow().counter(), 4);
+        assert_eq!(graph.search(&5).unwrap().borrow().weighted_counter(), 3.5f32);
+
+        match graph.entry(&5) {
+            Entry::Occupied(mut entry) => assert_eq!(entry.set_counter(1), None),
+            Entry::Vacant(_) => panic!("5 should be occupied")
+        }
+        assert!(matches!(graph.entry(&5).and_modify(|counter| *counter = 1), Entry::Occupied(_)));
+        assert_eq!(graph.search(&5).unwrap().borrow().counter(), 4);
+
+        assert!(graph.remove_record(&5, 10));
+        assert_eq!(graph.search(&5).unwrap().borrow().weighted_counter(), 2.5f32);
+        assert_eq!(graph.remove(&5), Some(2));
+        assert_eq!(graph.remove(&5), Some(1));
+        assert_eq!(graph.remove(&5), None);
+        assert_eq!(graph.remove_weighted(&5, 1.0f32), None);
+        assert_eq!(graph.search(&5).unwrap().borrow().weighted_counter(), 1.0f32);
+        assert_eq!(graph.records(&5).into_iter().collect::<Vec<_>>(), vec![11]);
+        assert!(graph.remove_record(&5, 11));
+        assert!(graph.search(&5).is_none());
+        assert_eq!(graph.count_elements_agg(), 19);
+        assert_eq!(graph.validate(), Ok(()));
+
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        let mut other = ASAGraph::<i32, 3>::new("other");
+        graph.insert_record(&1, 100);
+        graph.insert(&1);
+        other.insert_record(&1, 100);
+        other.insert_record(&1, 101);
+        let counters = |graph: &ASAGraph<i32, 3>| graph.search(&1)
+            .map(|e| (e.borrow().counter(), e.borrow().records().cloned().unwrap_or_default().into_iter().collect::<Vec<_>>()));
+
+        assert_eq!(counters(&graph.intersection(&other, CounterSemantics::Min)), Some((1, vec![100])));
+        assert_eq!(counters(&graph.intersection(&other, CounterSemantics::Sum)), Some((3, vec![100, 101])));
+        assert_eq!(counters(&graph.difference(&other)), Some((1, vec![])));
+        assert_eq!(counters(&other.difference(&graph)), Some((1, vec![101])));
+        assert_eq!(counters(&graph.symmetric_difference(&other)), Some((2, vec![101])));
+        graph.merge(&other);
+        assert_eq!(counters(&graph), Some((3, vec![100, 101])));
+        assert_eq!(graph.validate(), Ok(()));
+    }
+
+    #[test]
+    fn bulk_records() {
+        let data: Vec<(i32, usize)> = (0..200).map(|record| (record as i32 % 30, record)).chain([(7, 7)]).collect();
+        let bulk = ASAGraph::<i32, 3>::new_bulk_records("test", &data);
+        assert_eq!(bulk.validate(), Ok(()));
+        assert_eq!(bulk.count_elements_agg(), 200);
+        assert_eq!(bulk.records(&7).into_iter().collect::<Vec<_>>(), vec![7, 37, 67, 97, 127, 157, 187]);
+        assert_eq!(bulk.search(&7).unwrap().borrow().counter(), 7);
+        assert_eq!(bulk.records_in_range(..).len(), 200);
+
+        for threads in [1, 3, 8] {
+            let parallel = ASAGraph::<i32, 3>::new_parallel_records("test", &data, threads);
+            assert_eq!(parallel.validate(), Ok(()));
+            let elements = |graph: &ASAGraph<i32, 3>| graph.iter()
+                .map(|e| (e.borrow().key, e.borrow().counter, e.borrow().records.clone()))
+                .collect::<Vec<_>>();
+            assert_eq!(elements(&parallel), elements(&bulk));
+        }
+        assert!(ASAGraph::<i32, 3>::new_bulk("test", &[1, 2]).search(&1).unwrap().borrow().records().is_none());
+    }
+
+    graph_tests!(ASAGraph);
+
+    differential_tests!(ASAGraph, |graph: &mut ASAGraph<i32, ORDER>, model: &mut BTreeMap<i32, usize>, key: i32, is_all: bool| {
+        let counter = model.get(&key).cloned();
+        if is_all {
+            assert_eq!(graph.remove_all(&key).map(|e| e.borrow().counter), counter, "remove_all {}", key);
+            model.remove(&key);
+        } else {
+            assert_eq!(graph.remove(&key), counter.map(|counter| counter - 1), "remove {}", key);
+            match counter {
+                Some(1) => { model.remove(&key); },
+                Some(counter) => { model.insert(key, counter - 1); },
+                None => {}
+            }
+        }
+    });
+
 }
\ No newline at end of file
@@ -1,4 +1,8 @@
 pub mod graph;
 pub mod node;
 pub mod element;
-pub mod sensor;
\ No newline at end of file
+pub mod sensor;
+pub mod entry;
+pub mod cursor;
+pub mod validation;
+pub mod snapshot;
\ No newline at end of file
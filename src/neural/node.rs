@@ -2,7 +2,7 @@ use std::{
     fmt::{ Display, Formatter, Result },
     rc::{ Rc, Weak },
     cell::{ RefCell, Ref, RefMut },
-    cmp::Ordering::*,
+    cmp::{ Ordering, Ordering::* },
     marker::PhantomData
 };
 
@@ -12,17 +12,24 @@ use bionet_common::{
     data::{ DataDeductor, DataTypeValue }
 };
 
-use super::element::Element;
+use super::{
+    element::Element,
+    snapshot::SnapshotNode
+};
 
 #[derive(Clone)]
 pub struct Node<Key, const ORDER: usize>
 where Key: SensorData, [(); ORDER + 1]: {
     pub(crate) size: usize,
     pub(crate) is_leaf: bool,
+    pub(crate) count_unique: usize,
+    pub(crate) count_agg: usize,
+    pub(crate) weighted_count_agg: f32,
     pub(crate) parent: Option<Weak<RefCell<Node<Key, ORDER>>>>,
     pub(crate) keys: [Option<Key>; ORDER],
     pub(crate) elements: [Option<Rc<RefCell<Element<Key, ORDER>>>>; ORDER],
-    pub(crate) children: [Option<Rc<RefCell<Node<Key, ORDER>>>>; ORDER + 1]
+    pub(crate) children: [Option<Rc<RefCell<Node<Key, ORDER>>>>; ORDER + 1],
+    pub(crate) frozen: Option<Weak<SnapshotNode<Key>>>
 }
 
 impl<Key, const ORDER: usize> Node<Key, ORDER> 
@@ -38,15 +45,19 @@ where
         Node {
             size: 0,
             is_leaf,
+            count_unique: 0,
+            count_agg: 0,
+            weighted_count_agg: 0.0f32,
             parent,
             keys: [(); ORDER].map(|_| None),
             elements: [(); ORDER].map(|_| None),
-            children: [(); ORDER + 1].map(|_| None)
+            children: [(); ORDER + 1].map(|_| None),
+            frozen: None
         }
     }
 
     pub fn find_element(&self, key: &Key, algorithm: SearchAlgorithm)
-    -> Option<Ref<Element<Key, ORDER>>> {
+    -> Option<Ref<'_, Element<Key, ORDER>>> {
         match algorithm {
             SearchAlgorithm::LeftSearch => {
                 for i in 0..self.size {
@@ -54,7 +65,7 @@ where
                         return Some(self.elements[i].as_ref().unwrap().as_ref().borrow()) 
                     }
                 }
-                return None
+                None
             },
             SearchAlgorithm::RightSearch => {
                 for i in (0..self.size).rev() {
@@ -62,7 +73,7 @@ where
                         return Some(self.elements[i].as_ref().unwrap().as_ref().borrow()) 
                     }
                 }
-                return None
+                None
             },
             SearchAlgorithm::BinarySearch => {
                 let mut left_index = 0;
@@ -80,13 +91,13 @@ where
                         );
                     }
                 }
-                return None
+                None
             }
         }
     }
 
     pub fn find_element_mut(&self, key: &Key, algorithm: SearchAlgorithm)
-    -> Option<RefMut<Element<Key, ORDER>>> {
+    -> Option<RefMut<'_, Element<Key, ORDER>>> {
         match algorithm {
             SearchAlgorithm::LeftSearch => {
                 for i in 0..self.size {
@@ -94,7 +105,7 @@ where
                         return Some(self.elements[i].as_ref().unwrap().as_ref().borrow_mut()) 
                     }
                 }
-                return None
+                None
             },
             SearchAlgorithm::RightSearch => {
                 for i in (0..self.size).rev() {
@@ -102,7 +113,7 @@ where
                         return Some(self.elements[i].as_ref().unwrap().as_ref().borrow_mut()) 
                     }
                 }
-                return None
+                None
             },
             SearchAlgorithm::BinarySearch => {
                 let mut left_index = 0;
@@ -120,7 +131,7 @@ where
                         );
                     }
                 }
-                return None
+                None
             }
         }
     }
@@ -137,10 +148,11 @@ where
         );
         right_node.parent = Some(Rc::downgrade(node_ptr));
         
-        right_node.size = Self::MIN_ELEMENTS;
-        left_node.borrow_mut().size = Self::MIN_ELEMENTS;
+        let right_size = ORDER - Self::T_OFFSET;
+        right_node.size = right_size;
+        left_node.borrow_mut().size = Self::MID_INDEX;
 
-        for i in 0..Self::MIN_ELEMENTS {
+        for i in 0..right_size {
             right_node.elements[i] = left_node.borrow_mut().elements[Self::T_OFFSET + i].take();
             right_node.keys[i] = left_node.borrow_mut().keys[Self::T_OFFSET + i].take();
         }
@@ -158,7 +170,7 @@ where
             node_ptr.borrow().children[child_index + 1].as_ref().unwrap().clone();
         
         if !left_node.borrow().is_leaf {
-            for i in 0..Self::MIN_CHILDREN {
+            for i in 0..=right_size {
                 right_node.borrow_mut().children[i] = 
                     left_node.borrow_mut().children[Self::T_OFFSET + i].take();
                 right_node.borrow_mut().children[i].as_ref().unwrap().borrow_mut().parent 
@@ -171,6 +183,10 @@ where
         node_ptr.borrow_mut().keys[child_index] = 
             left_node.borrow_mut().keys[Self::MID_INDEX].take();
         node_ptr.borrow_mut().size += 1;
+
+        left_node.borrow_mut().update_counts();
+        right_node.borrow_mut().update_counts();
+        node_ptr.borrow_mut().update_counts();
     }
 
     pub(crate) fn insert_existing_key(&self, key: &Key, left_search: bool)
@@ -189,6 +205,7 @@ where
             if index < self.size && key.equals(current_key) {
                 let element = self.elements[index].as_ref().unwrap().clone();
                 element.borrow_mut().counter += 1;
+                element.borrow_mut().weighted_counter += 1.0f32;
                 return (Some(element), index)
             }
         } else {
@@ -203,12 +220,14 @@ where
             } else if key.equals(current_key) {
                 let element = self.elements[index].as_ref().unwrap().clone();
                 element.borrow_mut().counter += 1;
+                element.borrow_mut().weighted_counter += 1.0f32;
                 return (Some(element), index)
             }
 
             if index < self.size && key.equals(current_key) {
                 let element = self.elements[index].as_ref().unwrap().clone();
                 element.borrow_mut().counter += 1;
+                element.borrow_mut().weighted_counter += 1.0f32;
                 return (Some(element), index)
             }
         }
@@ -251,18 +270,12 @@ where
             if index == 0 {
                 if let Some(next) = node.borrow().elements[1].as_ref() {
                     next_ptr = Some(next.clone());
-                    prev_ptr = match next.as_ref().borrow().prev.as_ref() {
-                        Some(v) => Some(v.0.upgrade().unwrap()),
-                        None => None
-                    };
+                    prev_ptr = next.as_ref().borrow().prev.as_ref().map(|v| v.0.upgrade().unwrap());
                 }
             } else {
                 if let Some(prev) = node.borrow().elements[index - 1].as_ref() {
                     prev_ptr = Some(prev.clone());
-                    next_ptr = match prev.as_ref().borrow().next.as_ref() {
-                        Some(e) => Some(e.0.upgrade().unwrap().clone()),
-                        None => None
-                    };
+                    next_ptr = prev.as_ref().borrow().next.as_ref().map(|e| e.0.upgrade().unwrap().clone());
                 }
             }
         }
@@ -271,18 +284,27 @@ where
             &new_element, prev_ptr.as_ref(), next_ptr.as_ref(), range
         );
         node.borrow_mut().size += 1;
+        Self::update_counts_upwards(node);
 
         new_element
     }
 
-    pub const MIN_CHILDREN: usize = (ORDER + 1) / 2;
+    fn key_ordering(key: &Key, other: &Key) -> Option<Ordering> { key.partial_compare(other) }
+
+    fn clone_key(key: &Key) -> Key { *dyn_clone::clone_box(key) }
+
+    fn clear_frozen(&mut self) { self.frozen = None; }
+
+    node_algorithms!(Rc, RefCell, Element<Key, ORDER>);
+
+    pub const MIN_CHILDREN: usize = ORDER.div_ceil(2);
     pub const MAX_CHILDREN: usize = ORDER + 1;
-    pub const MIN_ELEMENTS: usize = (ORDER + 1) / 2 - 1;
+    pub const MIN_ELEMENTS: usize = ORDER.div_ceil(2) - 1;
     pub const MAX_ELEMENTS: usize = ORDER;
-    pub const MIN_KEYS: usize = (ORDER + 1) / 2 - 1;
+    pub const MIN_KEYS: usize = ORDER.div_ceil(2) - 1;
     pub const MAX_KEYS: usize = ORDER;
-    pub(crate) const MID_INDEX: usize = (ORDER + 1) / 2 - 1;
-    pub(crate) const T_OFFSET: usize = (ORDER + 1) / 2;
+    pub(crate) const MID_INDEX: usize = ORDER.div_ceil(2) - 1;
+    pub(crate) const T_OFFSET: usize = ORDER.div_ceil(2);
 }
 
 impl<Key, const ORDER: usize> Display for Node<Key, ORDER> 
@@ -320,6 +342,7 @@ where Key: SensorData, [(); ORDER + 1]: {
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_borrow, clippy::bool_assert_comparison)]
 mod tests {
     use std::{
         rc::Rc,
@@ -0,0 +1,324 @@
+use std::{
+    fmt::{ Display, Formatter, Result as FmtResult },
+    rc::Rc,
+    cell::RefCell,
+    cmp::Ordering::*,
+    marker::PhantomData
+};
+
+use bionet_common::{
+    sensor::SensorData,
+    data::{ DataDeductor, DataTypeValue }
+};
+
+use super::{
+    element::Element,
+    node::Node,
+    graph::ASAGraph
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationError<Key> {
+    NodeSize { depth: usize, size: usize },
+    MissingChild { depth: usize, index: usize },
+    ParentPointer { depth: usize, index: usize },
+    LeafDepth { expected: usize, found: usize },
+    AggregateCounts { depth: usize },
+    MissingElement { key: Key },
+    KeyMismatch { key: Key, element_key: Key },
+    UnsortedKeys { key: Key, next_key: Key },
+    BrokenChain { key: Key },
+    IncompleteChain { tree_elements: usize, chain_elements: usize },
+    Weight { key: Key, stored: f32, expected: f32 },
+    Records { key: Key, counter: usize, records: usize },
+    ElementMin,
+    ElementMax,
+    KeyMin,
+    KeyMax
+}
+
+impl<Key: Display> Display for ValidationError<Key> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            ValidationError::NodeSize { depth, size } => {
+                write!(f, "node at depth {} has {} keys", depth, size)
+            },
+            ValidationError::MissingChild { depth, index } => {
+                write!(f, "node at depth {} is missing child {}", depth, index)
+            },
+            ValidationError::ParentPointer { depth, index } => {
+                write!(f, "child {} of node at depth {} has a wrong parent pointer", index, depth)
+            },
+            ValidationError::LeafDepth { expected, found } => {
+                write!(f, "leaf at depth {} while other leaves are at depth {}", found, expected)
+            },
+            ValidationError::AggregateCounts { depth } => {
+                write!(f, "node at depth {} has stale aggregate counts", depth)
+            },
+            ValidationError::MissingElement { key } => write!(f, "key {} has no element", key),
+            ValidationError::KeyMismatch { key, element_key } => {
+                write!(f, "key {} points to element with key {}", key, element_key)
+            },
+            ValidationError::UnsortedKeys { key, next_key } => {
+                write!(f, "key {} is followed by key {}", key, next_key)
+            },
+            ValidationError::BrokenChain { key } => {
+                write!(f, "element chain is broken at key {}", key)
+            },
+            ValidationError::IncompleteChain { tree_elements, chain_elements } => {
+                write!(f, "element chain has {} of {} elements", chain_elements, tree_elements)
+            },
+            ValidationError::Weight { key, stored, expected } => {
+                write!(f, "element with key {} has weight {} instead of {}", key, stored, expected)
+            },
+            ValidationError::Records { key, counter, records } => {
+                write!(f, "element with key {} has {} records but counter {}", key, records, counter)
+            },
+            ValidationError::ElementMin => write!(f, "element_min is not the first element"),
+            ValidationError::ElementMax => write!(f, "element_max is not the last element"),
+            ValidationError::KeyMin => write!(f, "key_min is not the smallest key"),
+            ValidationError::KeyMax => write!(f, "key_max is not the largest key")
+        }
+    }
+}
+
+const WEIGHT_TOLERANCE: f32 = 1.0e-5;
+
+fn is_same<T>(element: Option<&Rc<T>>, expected: Option<&Rc<T>>) -> bool {
+    match (element, expected) {
+        (Some(element), Some(expected)) => Rc::ptr_eq(element, expected),
+        (None, None) => true,
+        _ => false
+    }
+}
+
+impl<Key, const ORDER: usize> ASAGraph<Key, ORDER>
+where
+    Key: SensorData,
+    [(); ORDER + 1]:,
+    PhantomData<Key>: DataDeductor,
+    DataTypeValue: From<Key>
+{
+    pub fn validate(&self) -> Result<(), Vec<ValidationError<Key>>> {
+        let mut errors = Vec::new();
+        let mut elements = Vec::new();
+        let mut leaf_depth = None;
+
+        if self.root.borrow().parent.is_some() {
+            errors.push(ValidationError::ParentPointer { depth: 0, index: 0 });
+        }
+        Self::validate_node(&self.root, 0, &mut leaf_depth, &mut elements, &mut errors);
+
+        for pair in elements.windows(2) {
+            let (key, next_key) = (&pair[0].borrow().key, &pair[1].borrow().key);
+            if key.partial_compare(next_key) != Some(Less) {
+                errors.push(ValidationError::UnsortedKeys {
+                    key: *dyn_clone::clone_box(key), next_key: *dyn_clone::clone_box(next_key)
+                });
+            }
+        }
+
+        self.validate_chain(&elements, &mut errors);
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    fn validate_node(
+        node: &Rc<RefCell<Node<Key, ORDER>>>,
+        depth: usize,
+        leaf_depth: &mut Option<usize>,
+        elements: &mut Vec<Rc<RefCell<Element<Key, ORDER>>>>,
+        errors: &mut Vec<ValidationError<Key>>
+    ) {
+        let node_ref = node.borrow();
+        let size = node_ref.size;
+        let is_size_valid = if depth == 0 {
+            size <= Node::<Key, ORDER>::MAX_KEYS && (size > 0 || node_ref.is_leaf)
+        } else {
+            size >= Node::<Key, ORDER>::MIN_KEYS && size <= Node::<Key, ORDER>::MAX_KEYS
+        };
+        if !is_size_valid {
+            errors.push(ValidationError::NodeSize { depth, size });
+            if size > ORDER { return }
+        }
+
+        let first_element = elements.len();
+        let mut count_agg = 0;
+        for index in 0..=size {
+            if !node_ref.is_leaf {
+                match &node_ref.children[index] {
+                    Some(child) => {
+                        let parent = child.borrow().parent.as_ref().and_then(|parent| parent.upgrade());
+                        if !parent.is_some_and(|parent| Rc::ptr_eq(&parent, node)) {
+                            errors.push(ValidationError::ParentPointer { depth, index });
+                        }
+                        Self::validate_node(child, depth + 1, leaf_depth, elements, errors);
+                        count_agg += child.borrow().count_agg;
+                    },
+                    None => errors.push(ValidationError::MissingChild { depth, index })
+                }
+            }
+            if index == size { break }
+
+            let key = match &node_ref.keys[index] {
+                Some(key) => key,
+                None => continue
+            };
+            match &node_ref.elements[index] {
+                Some(element) => {
+                    let element_key = &element.borrow().key;
+                    if !element_key.equals(key) {
+                        errors.push(ValidationError::KeyMismatch {
+                            key: *dyn_clone::clone_box(key), element_key: *dyn_clone::clone_box(element_key)
+                        });
+                    }
+                    let (counter, records) = (element.borrow().counter, element.borrow().records().map_or(0, |r| r.len()));
+                    if records > counter {
+                        errors.push(ValidationError::Records { key: *dyn_clone::clone_box(key), counter, records });
+                    }
+                    count_agg += counter;
+                    elements.push(element.clone());
+                },
+                None => errors.push(ValidationError::MissingElement { key: *dyn_clone::clone_box(key) })
+            }
+        }
+
+        if node_ref.is_leaf {
+            match *leaf_depth {
+                Some(expected) if expected != depth => {
+                    errors.push(ValidationError::LeafDepth { expected, found: depth });
+                },
+                Some(_) => {},
+                None => *leaf_depth = Some(depth)
+            }
+        }
+
+        if node_ref.count_unique != elements.len() - first_element || node_ref.count_agg != count_agg {
+            errors.push(ValidationError::AggregateCounts { depth });
+        }
+    }
+
+    fn validate_chain(
+        &self,
+        elements: &[Rc<RefCell<Element<Key, ORDER>>>],
+        errors: &mut Vec<ValidationError<Key>>
+    ) {
+        if !is_same(self.element_min.as_ref(), elements.first()) { errors.push(ValidationError::ElementMin) }
+        if !is_same(self.element_max.as_ref(), elements.last()) { errors.push(ValidationError::ElementMax) }
+        let is_same_key = |key: &Option<Key>, element: Option<&Rc<RefCell<Element<Key, ORDER>>>>| {
+            match (key, element) {
+                (Some(key), Some(element)) => key.equals(&element.borrow().key),
+                (None, None) => true,
+                _ => false
+            }
+        };
+        if !is_same_key(&self.key_min, elements.first()) { errors.push(ValidationError::KeyMin) }
+        if !is_same_key(&self.key_max, elements.last()) { errors.push(ValidationError::KeyMax) }
+
+        let range = self.key_range();
+        let mut chain_elements = 0;
+        let mut prev: Option<Rc<RefCell<Element<Key, ORDER>>>> = None;
+        let mut current = self.element_min.clone();
+        while let Some(element) = current {
+            if chain_elements == elements.len() || !Rc::ptr_eq(&element, &elements[chain_elements]) {
+                errors.push(ValidationError::BrokenChain { key: *dyn_clone::clone_box(&element.borrow().key) });
+                break
+            }
+            let element_prev = element.borrow().prev.as_ref().and_then(|(prev, _)| prev.upgrade());
+            if !is_same(element_prev.as_ref(), prev.as_ref()) {
+                errors.push(ValidationError::BrokenChain { key: *dyn_clone::clone_box(&element.borrow().key) });
+            } else if let Some(prev) = &prev {
+                let expected = element.borrow().weight(&prev.borrow(), range);
+                let prev_weight = element.borrow().prev.as_ref().map(|(_, weight)| *weight);
+                let next_weight = prev.borrow().next.as_ref().map(|(_, weight)| *weight);
+                match (prev_weight, next_weight) {
+                    (Some(prev_weight), Some(next_weight)) => {
+                        for stored in [prev_weight, next_weight] {
+                            if (stored - expected).abs() > WEIGHT_TOLERANCE {
+                                let key = *dyn_clone::clone_box(&element.borrow().key);
+                                errors.push(ValidationError::Weight { key, stored, expected });
+                            }
+                        }
+                    },
+                    _ => {
+                        let key = *dyn_clone::clone_box(&element.borrow().key);
+                        errors.push(ValidationError::BrokenChain { key });
+                    }
+                }
+            }
+            chain_elements += 1;
+            current = element.borrow().next.as_ref().and_then(|(next, _)| next.upgrade());
+            prev = Some(element);
+        }
+
+        if chain_elements != elements.len() {
+            errors.push(ValidationError::IncompleteChain { tree_elements: elements.len(), chain_elements });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::{ Rc, Weak };
+
+    use super::{ super::graph::ASAGraph, ValidationError };
+
+    #[test]
+    fn validate() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        assert_eq!(graph.validate(), Ok(()));
+        for i in 0..100 { graph.insert(&(i * 7 % 50)); }
+        assert_eq!(graph.validate(), Ok(()));
+        graph.remove_range(10..30);
+        graph.remove(&49);
+        graph.remove_all(&0);
+        assert_eq!(graph.validate(), Ok(()));
+
+        let element_max = graph.element_max.replace(graph.search(&1).unwrap());
+        graph.root.borrow_mut().count_agg += 1;
+        let errors = graph.validate().unwrap_err();
+        assert_eq!(errors, vec![ValidationError::AggregateCounts { depth: 0 }, ValidationError::ElementMax]);
+        assert_eq!(errors[1].to_string(), "element_max is not the last element");
+        graph.element_max = element_max;
+        graph.root.borrow_mut().count_agg -= 1;
+
+        let element = graph.search(&35).unwrap();
+        let next = element.borrow_mut().next.take().unwrap();
+        assert_eq!(
+            graph.validate().unwrap_err(),
+            vec![ValidationError::IncompleteChain { tree_elements: 29, chain_elements: 15 }]
+        );
+        element.borrow_mut().next = Some((next.0.clone(), 0.5f32));
+        let errors = graph.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], ValidationError::Weight { key: 36, stored, .. } if stored == 0.5f32));
+        element.borrow_mut().next = Some(next);
+        assert_eq!(graph.validate(), Ok(()));
+
+        let other = graph.search(&40).unwrap();
+        other.borrow_mut().next = Some((Rc::downgrade(&element), 1.0f32));
+        let errors = graph.validate().unwrap_err();
+        assert!(errors.contains(&ValidationError::BrokenChain { key: 35 }));
+    }
+
+    #[test]
+    fn validate_broken_next() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 0..20 { graph.insert(&i); }
+
+        let element = graph.search(&5).unwrap();
+        let next = element.borrow_mut().next.take().unwrap();
+        let errors = graph.validate().unwrap_err();
+        assert_eq!(errors, vec![ValidationError::IncompleteChain { tree_elements: 20, chain_elements: 6 }]);
+
+        element.borrow_mut().next = Some((Weak::new(), next.1));
+        let errors = graph.validate().unwrap_err();
+        assert_eq!(errors, vec![ValidationError::IncompleteChain { tree_elements: 20, chain_elements: 6 }]);
+
+        let element_max = graph.element_max.clone().unwrap();
+        element.borrow_mut().next = Some(next);
+        element_max.borrow_mut().next = Some((Rc::downgrade(&graph.search(&0).unwrap()), 1.0f32));
+        let errors = graph.validate().unwrap_err();
+        assert!(errors.contains(&ValidationError::BrokenChain { key: 0 }));
+    }
+}
\ No newline at end of file
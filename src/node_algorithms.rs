@@ -0,0 +1,617 @@
+// Rebalancing, removal, split/join and bulk-build algorithms shared by the simple,
+// neural and concurrent nodes. The macro is expanded inside each `impl Node` with the
+// pointer and cell types the node is built from (`Rc`/`RefCell` or `Arc`/`RwLock`);
+// the impl provides `key_ordering` and `clone_key` for its key type and `clear_frozen`
+// to drop a cached snapshot.
+macro_rules! node_algorithms {
+    ($ptr:ident, $cell:ident, $element:ty) => {
+        fn remove_extreme(
+            node: &$ptr<$cell<Self>>, is_max: bool
+        ) -> $ptr<$cell<$element>> {
+            let mut node = node.clone();
+            loop {
+                let node_size = node.borrow().size;
+                if node.borrow().is_leaf {
+                    let index = if is_max { node_size - 1 } else { 0 };
+                    let element = node.borrow_mut().take_at(index).1;
+                    Self::update_counts_upwards(&node);
+                    return element
+                }
+                let index = Self::fill_child(&node, if is_max { node_size } else { 0 });
+                let child = node.borrow().children[index].as_ref().unwrap().clone();
+                node = child;
+            }
+        }
+
+        pub(crate) fn remove_key(
+            node: &$ptr<$cell<Self>>, key: &Key
+        ) -> Option<$ptr<$cell<$element>>> {
+            let mut node = node.clone();
+            loop {
+                let (index, is_found, is_leaf) = {
+                    let node_ref = node.borrow();
+                    let index = node_ref.lower_bound_index(key);
+                    let is_found = index < node_ref.size
+                        && Self::key_ordering(key, node_ref.keys[index].as_ref().unwrap()) == Some(Ordering::Equal);
+                    (index, is_found, node_ref.is_leaf)
+                };
+
+                if is_leaf {
+                    if !is_found {
+                        Self::update_counts_upwards(&node);
+                        return None
+                    }
+                    let element = node.borrow_mut().take_at(index).1;
+                    Self::update_counts_upwards(&node);
+                    return Some(element)
+                }
+
+                if is_found {
+                    let left_child = node.borrow().children[index].as_ref().unwrap().clone();
+                    let right_child = node.borrow().children[index + 1].as_ref().unwrap().clone();
+                    if left_child.borrow().size > Self::MIN_KEYS {
+                        let predecessor = Self::remove_extreme(&left_child, true);
+                        let element = node.borrow_mut().replace_at(index, predecessor);
+                        Self::update_counts_upwards(&node);
+                        return Some(element)
+                    } else if right_child.borrow().size > Self::MIN_KEYS {
+                        let successor = Self::remove_extreme(&right_child, false);
+                        let element = node.borrow_mut().replace_at(index, successor);
+                        Self::update_counts_upwards(&node);
+                        return Some(element)
+                    } else {
+                        Self::merge_children(&node, index);
+                        node = left_child;
+                    }
+                } else {
+                    let index = Self::fill_child(&node, index);
+                    let child = node.borrow().children[index].as_ref().unwrap().clone();
+                    node = child;
+                }
+            }
+        }
+
+        fn replace_at(
+            &mut self, index: usize, element: $ptr<$cell<$element>>
+        ) -> $ptr<$cell<$element>> {
+            self.keys[index] = Some(Self::clone_key(&element.borrow().key));
+            self.elements[index].replace(element).unwrap()
+        }
+
+        fn fill_child(node: &$ptr<$cell<Self>>, index: usize) -> usize {
+            let node_size = node.borrow().size;
+            let child_size = |i: usize| node.borrow().children[i].as_ref().unwrap().borrow().size;
+
+            if child_size(index) > Self::MIN_KEYS {
+                index
+            } else if index > 0 && child_size(index - 1) > Self::MIN_KEYS {
+                Self::rotate_right(node, index - 1);
+                index
+            } else if index < node_size && child_size(index + 1) > Self::MIN_KEYS {
+                Self::rotate_left(node, index);
+                index
+            } else if index < node_size {
+                Self::merge_children(node, index);
+                index
+            } else {
+                Self::merge_children(node, index - 1);
+                index - 1
+            }
+        }
+
+        fn rotate_right(node_ptr: &$ptr<$cell<Self>>, index: usize) {
+            let mut node = node_ptr.borrow_mut();
+            let left_ptr = node.children[index].as_ref().unwrap().clone();
+            let right_ptr = node.children[index + 1].as_ref().unwrap().clone();
+            let mut left = left_ptr.borrow_mut();
+            let mut right = right_ptr.borrow_mut();
+            let left_size = left.size;
+            let right_size = right.size;
+
+            for i in (0..right_size).rev() {
+                right.keys.swap(i, i + 1);
+                right.elements.swap(i, i + 1);
+            }
+            right.keys[0] = node.keys[index].take();
+            right.elements[0] = node.elements[index].take();
+            node.keys[index] = left.keys[left_size - 1].take();
+            node.elements[index] = left.elements[left_size - 1].take();
+
+            if !right.is_leaf {
+                for i in (0..=right_size).rev() {
+                    right.children.swap(i, i + 1);
+                }
+                let child = left.children[left_size].take().unwrap();
+                child.borrow_mut().parent = Some($ptr::downgrade(&right_ptr));
+                right.children[0] = Some(child);
+            }
+
+            left.size -= 1;
+            right.size += 1;
+            left.update_counts();
+            right.update_counts();
+            node.clear_frozen();
+        }
+
+        fn rotate_left(node_ptr: &$ptr<$cell<Self>>, index: usize) {
+            let mut node = node_ptr.borrow_mut();
+            let left_ptr = node.children[index].as_ref().unwrap().clone();
+            let right_ptr = node.children[index + 1].as_ref().unwrap().clone();
+            let mut left = left_ptr.borrow_mut();
+            let mut right = right_ptr.borrow_mut();
+            let left_size = left.size;
+            let right_size = right.size;
+
+            left.keys[left_size] = node.keys[index].take();
+            left.elements[left_size] = node.elements[index].take();
+            node.keys[index] = right.keys[0].take();
+            node.elements[index] = right.elements[0].take();
+            for i in 0..(right_size - 1) {
+                right.keys.swap(i, i + 1);
+                right.elements.swap(i, i + 1);
+            }
+
+            if !left.is_leaf {
+                let child = right.children[0].take().unwrap();
+                child.borrow_mut().parent = Some($ptr::downgrade(&left_ptr));
+                left.children[left_size + 1] = Some(child);
+                for i in 0..right_size {
+                    right.children.swap(i, i + 1);
+                }
+            }
+
+            left.size += 1;
+            right.size -= 1;
+            left.update_counts();
+            right.update_counts();
+            node.clear_frozen();
+        }
+
+        pub(crate) fn merge_children(node_ptr: &$ptr<$cell<Self>>, index: usize) {
+            let mut node = node_ptr.borrow_mut();
+            let node_size = node.size;
+            let left_ptr = node.children[index].as_ref().unwrap().clone();
+            let right_ptr = node.children[index + 1].take().unwrap();
+            {
+                let mut left = left_ptr.borrow_mut();
+                let mut right = right_ptr.borrow_mut();
+                let left_size = left.size;
+                let right_size = right.size;
+
+                left.keys[left_size] = node.keys[index].take();
+                left.elements[left_size] = node.elements[index].take();
+                for i in 0..right_size {
+                    left.keys[left_size + 1 + i] = right.keys[i].take();
+                    left.elements[left_size + 1 + i] = right.elements[i].take();
+                }
+                if !left.is_leaf {
+                    for i in 0..=right_size {
+                        let child = right.children[i].take().unwrap();
+                        child.borrow_mut().parent = Some($ptr::downgrade(&left_ptr));
+                        left.children[left_size + 1 + i] = Some(child);
+                    }
+                }
+                left.size = left_size + right_size + 1;
+                right.size = 0;
+                left.update_counts();
+            }
+
+            for i in index..(node_size - 1) {
+                node.keys.swap(i, i + 1);
+                node.elements.swap(i, i + 1);
+            }
+            for i in (index + 1)..node_size {
+                node.children.swap(i, i + 1);
+            }
+            node.size -= 1;
+            node.clear_frozen();
+        }
+
+        pub(crate) fn lower_bound_index(&self, key: &Key) -> usize {
+            let mut index = 0;
+            while index < self.size
+                && Self::key_ordering(key, self.keys[index].as_ref().unwrap()) == Some(Greater) {
+                index += 1;
+            }
+            index
+        }
+
+        fn take_at(&mut self, index: usize) -> (Key, $ptr<$cell<$element>>) {
+            let key = self.keys[index].take().unwrap();
+            let element = self.elements[index].take().unwrap();
+            for i in index..(self.size - 1) {
+                self.keys.swap(i, i + 1);
+                self.elements.swap(i, i + 1);
+            }
+            self.size -= 1;
+            (key, element)
+        }
+
+        pub(crate) fn split(
+            node: &$ptr<$cell<Self>>, height: usize, key: &Key, include_key: bool
+        ) -> (($ptr<$cell<Self>>, usize), ($ptr<$cell<Self>>, usize)) {
+            let (index, node_size, is_leaf) = {
+                let node_ref = node.borrow();
+                let mut index = 0;
+                while index < node_ref.size {
+                    match Self::key_ordering(node_ref.keys[index].as_ref().unwrap(), key) {
+                        Some(Less) => index += 1,
+                        Some(Equal) if include_key => index += 1,
+                        _ => break
+                    }
+                }
+                (index, node_ref.size, node_ref.is_leaf)
+            };
+
+            let right_node = $ptr::new($cell::new(Self::new(is_leaf, None)));
+            if is_leaf {
+                {
+                    let mut node_ref = node.borrow_mut();
+                    let mut right_ref = right_node.borrow_mut();
+                    for i in index..node_size {
+                        right_ref.keys[i - index] = node_ref.keys[i].take();
+                        right_ref.elements[i - index] = node_ref.elements[i].take();
+                    }
+                    right_ref.size = node_size - index;
+                    node_ref.size = index;
+                    node_ref.parent = None;
+                    right_ref.update_counts();
+                    node_ref.update_counts();
+                }
+                return ((node.clone(), 0), (right_node, 0))
+            }
+
+            let child = node.borrow_mut().children[index].take().unwrap();
+            let (child_left, child_right) = Self::split(&child, height - 1, key, include_key);
+
+            let right = if index == node_size {
+                child_right
+            } else {
+                let separator = {
+                    let mut node_ref = node.borrow_mut();
+                    let mut right_ref = right_node.borrow_mut();
+                    for i in (index + 1)..node_size {
+                        right_ref.keys[i - index - 1] = node_ref.keys[i].take();
+                        right_ref.elements[i - index - 1] = node_ref.elements[i].take();
+                    }
+                    for i in (index + 1)..=node_size {
+                        let child = node_ref.children[i].take().unwrap();
+                        child.borrow_mut().parent = Some($ptr::downgrade(&right_node));
+                        right_ref.children[i - index - 1] = Some(child);
+                    }
+                    right_ref.size = node_size - index - 1;
+                    right_ref.update_counts();
+                    (node_ref.keys[index].take().unwrap(), node_ref.elements[index].take().unwrap())
+                };
+                Self::join(child_right, separator, Self::normalize(&right_node, height))
+            };
+
+            let left = if index == 0 {
+                child_left
+            } else {
+                let separator = {
+                    let mut node_ref = node.borrow_mut();
+                    node_ref.size = index - 1;
+                    let separator = (
+                        node_ref.keys[index - 1].take().unwrap(),
+                        node_ref.elements[index - 1].take().unwrap()
+                    );
+                    node_ref.update_counts();
+                    separator
+                };
+                Self::join(Self::normalize(node, height), separator, child_left)
+            };
+
+            (left, right)
+        }
+
+        pub(crate) fn join(
+            left: ($ptr<$cell<Self>>, usize),
+            separator: (Key, $ptr<$cell<$element>>),
+            right: ($ptr<$cell<Self>>, usize)
+        ) -> ($ptr<$cell<Self>>, usize) {
+            let (left, left_height) = left;
+            let (right, right_height) = right;
+            let (separator_key, separator_element) = separator;
+
+            if left_height == right_height {
+                let left_size = left.borrow().size;
+                let right_size = right.borrow().size;
+                let root = $ptr::new($cell::new(Self::new(false, None)));
+                {
+                    let mut root_ref = root.borrow_mut();
+                    root_ref.keys[0] = Some(separator_key);
+                    root_ref.elements[0] = Some(separator_element);
+                    root_ref.children[0] = Some(left.clone());
+                    root_ref.children[1] = Some(right.clone());
+                    root_ref.size = 1;
+                }
+                left.borrow_mut().parent = Some($ptr::downgrade(&root));
+                right.borrow_mut().parent = Some($ptr::downgrade(&root));
+
+                if left_size + right_size < Self::MAX_KEYS {
+                    Self::merge_children(&root, 0);
+                    left.borrow_mut().parent = None;
+                    return (left, left_height)
+                }
+                Self::balance_children(&root, 0);
+                root.borrow_mut().update_counts();
+                return (root, left_height + 1)
+            }
+
+            let is_left_higher = left_height > right_height;
+            let (mut root, mut root_height, low, low_height) = if is_left_higher {
+                (left, left_height, right, right_height)
+            } else {
+                (right, right_height, left, left_height)
+            };
+
+            if root.borrow().size == Self::MAX_KEYS {
+                let new_root = $ptr::new($cell::new(Self::new(false, None)));
+                root.borrow_mut().parent = Some($ptr::downgrade(&new_root));
+                new_root.borrow_mut().children[0] = Some(root);
+                Self::split_child(&new_root, 0);
+                root = new_root;
+                root_height += 1;
+            }
+
+            let mut node = root.clone();
+            let mut height = root_height;
+            while height > low_height + 1 {
+                let mut index = if is_left_higher { node.borrow().size } else { 0 };
+                let child_size = node.borrow().children[index].as_ref().unwrap().borrow().size;
+                if child_size == Self::MAX_KEYS {
+                    Self::split_child(&node, index);
+                    if is_left_higher { index += 1 }
+                }
+                let child = node.borrow().children[index].as_ref().unwrap().clone();
+                node = child;
+                height -= 1;
+            }
+
+            let low_size = low.borrow().size;
+            low.borrow_mut().parent = Some($ptr::downgrade(&node));
+            let index = {
+                let mut node_ref = node.borrow_mut();
+                let node_size = node_ref.size;
+                if is_left_higher {
+                    node_ref.keys[node_size] = Some(separator_key);
+                    node_ref.elements[node_size] = Some(separator_element);
+                    node_ref.children[node_size + 1] = Some(low);
+                    node_ref.size += 1;
+                    node_size
+                } else {
+                    for i in (0..node_size).rev() {
+                        node_ref.keys.swap(i, i + 1);
+                        node_ref.elements.swap(i, i + 1);
+                    }
+                    for i in (0..=node_size).rev() {
+                        node_ref.children.swap(i, i + 1);
+                    }
+                    node_ref.keys[0] = Some(separator_key);
+                    node_ref.elements[0] = Some(separator_element);
+                    node_ref.children[0] = Some(low);
+                    node_ref.size += 1;
+                    0
+                }
+            };
+
+            if low_size < Self::MIN_KEYS {
+                let sibling_index = if is_left_higher { index } else { index + 1 };
+                let sibling_size =
+                    node.borrow().children[sibling_index].as_ref().unwrap().borrow().size;
+                if low_size + sibling_size < Self::MAX_KEYS {
+                    Self::merge_children(&node, index);
+                } else {
+                    Self::balance_children(&node, index);
+                }
+            }
+
+            root.borrow_mut().parent = None;
+            Self::update_counts_upwards(&node);
+            (root, root_height)
+        }
+
+        pub(crate) fn concat(
+            left: ($ptr<$cell<Self>>, usize),
+            right: ($ptr<$cell<Self>>, usize)
+        ) -> ($ptr<$cell<Self>>, usize) {
+            if right.0.borrow().size == 0 { return left }
+            if left.0.borrow().size == 0 { return right }
+            let separator_element = Self::remove_extreme(&right.0, false);
+            let separator_key = Self::clone_key(&separator_element.borrow().key);
+            let right = Self::normalize(&right.0, right.1);
+            Self::join(left, (separator_key, separator_element), right)
+        }
+
+        fn balance_children(node: &$ptr<$cell<Self>>, index: usize) {
+            let child_size = |i: usize| node.borrow().children[i].as_ref().unwrap().borrow().size;
+            while child_size(index) < Self::MIN_KEYS {
+                Self::rotate_left(node, index);
+            }
+            while child_size(index + 1) < Self::MIN_KEYS {
+                Self::rotate_right(node, index);
+            }
+        }
+
+        fn normalize(
+            node: &$ptr<$cell<Self>>, height: usize
+        ) -> ($ptr<$cell<Self>>, usize) {
+            if node.borrow().size == 0 && !node.borrow().is_leaf {
+                let child = node.borrow_mut().children[0].take().unwrap();
+                child.borrow_mut().parent = None;
+                (child, height - 1)
+            } else {
+                node.borrow_mut().parent = None;
+                (node.clone(), height)
+            }
+        }
+
+        pub(crate) fn extreme_element(
+            node: &$ptr<$cell<Self>>, is_max: bool
+        ) -> Option<$ptr<$cell<$element>>> {
+            let (node, index) = Self::extreme_position(node, is_max)?;
+            let element = node.borrow().elements[index].as_ref().unwrap().clone();
+            Some(element)
+        }
+
+        pub(crate) fn extreme_position(
+            node: &$ptr<$cell<Self>>, is_max: bool
+        ) -> Option<($ptr<$cell<Self>>, usize)> {
+            let mut node = node.clone();
+            loop {
+                let node_size = node.borrow().size;
+                if node.borrow().is_leaf {
+                    if node_size == 0 { return None }
+                    let index = if is_max { node_size - 1 } else { 0 };
+                    return Some((node, index))
+                }
+                let index = if is_max { node_size } else { 0 };
+                let child = node.borrow().children[index].as_ref().unwrap().clone();
+                node = child;
+            }
+        }
+
+        pub(crate) fn find_node(
+            node: &$ptr<$cell<Self>>, key: &Key
+        ) -> Option<($ptr<$cell<Self>>, usize)> {
+            Self::find_node_or_leaf(node, key).ok()
+        }
+
+        // Like find_node, but a missing key yields the leaf it would be inserted into.
+        pub(crate) fn find_node_or_leaf(
+            node: &$ptr<$cell<Self>>, key: &Key
+        ) -> std::result::Result<($ptr<$cell<Self>>, usize), $ptr<$cell<Self>>> {
+            let mut node = node.clone();
+            loop {
+                let child = {
+                    let node_ref = node.borrow();
+                    let index = node_ref.lower_bound_index(key);
+                    if index < node_ref.size
+                        && Self::key_ordering(key, node_ref.keys[index].as_ref().unwrap()) == Some(Equal) {
+                        return Ok((node.clone(), index))
+                    }
+                    if node_ref.is_leaf { return Err(node.clone()) }
+                    node_ref.children[index].as_ref().unwrap().clone()
+                };
+                node = child;
+            }
+        }
+
+        pub(crate) fn update_counts(&mut self) {
+            let mut count_unique = self.size;
+            let mut count_agg = 0;
+            let mut weighted_count_agg = 0.0f32;
+            for i in 0..self.size {
+                let element = self.elements[i].as_ref().unwrap().borrow();
+                count_agg += element.counter;
+                weighted_count_agg += element.weighted_counter;
+            }
+            if !self.is_leaf {
+                for child in self.children[..=self.size].iter().flatten() {
+                    let child = child.borrow();
+                    count_unique += child.count_unique;
+                    count_agg += child.count_agg;
+                    weighted_count_agg += child.weighted_count_agg;
+                }
+            }
+            self.count_unique = count_unique;
+            self.count_agg = count_agg;
+            self.weighted_count_agg = weighted_count_agg;
+            self.clear_frozen();
+        }
+
+        pub(crate) fn update_counts_upwards(node: &$ptr<$cell<Self>>) {
+            let mut node = node.clone();
+            loop {
+                node.borrow_mut().update_counts();
+                let parent = match &node.borrow().parent {
+                    Some(parent) => parent.upgrade(),
+                    None => None
+                };
+                match parent {
+                    Some(parent) => node = parent,
+                    None => return
+                }
+            }
+        }
+
+        pub(crate) fn height(node: &$ptr<$cell<Self>>) -> usize {
+            let mut height = 0;
+            let mut node = node.clone();
+            while !node.borrow().is_leaf {
+                let child = node.borrow().children[0].as_ref().unwrap().clone();
+                node = child;
+                height += 1;
+            }
+            height
+        }
+
+        // Packs sorted elements into full leaves, then full parents level by level,
+        // so only the last two nodes of a level can be less than full.
+        pub(crate) fn build(elements: &[$ptr<$cell<$element>>]) -> $ptr<$cell<Self>> {
+            let mut nodes = Vec::new();
+            let mut separators = Vec::new();
+            let mut start = 0;
+            for size in Self::group_sizes(elements.len() + 1, Self::MAX_KEYS + 1, Self::MIN_KEYS + 1) {
+                let node = $ptr::new($cell::new(Self::new(true, None)));
+                {
+                    let mut node_ref = node.borrow_mut();
+                    for (i, element) in elements[start..(start + size - 1)].iter().enumerate() {
+                        node_ref.keys[i] = Some(Self::clone_key(&element.borrow().key));
+                        node_ref.elements[i] = Some(element.clone());
+                    }
+                    node_ref.size = size - 1;
+                    node_ref.update_counts();
+                }
+                nodes.push(node);
+                start += size - 1;
+                if start < elements.len() {
+                    separators.push(elements[start].clone());
+                    start += 1;
+                }
+            }
+
+            while nodes.len() > 1 {
+                let sizes = Self::group_sizes(nodes.len(), Self::MAX_CHILDREN, Self::MIN_CHILDREN);
+                let mut children = std::mem::take(&mut nodes).into_iter();
+                let mut child_separators = std::mem::take(&mut separators).into_iter();
+                for size in sizes {
+                    let node = $ptr::new($cell::new(Self::new(false, None)));
+                    {
+                        let mut node_ref = node.borrow_mut();
+                        for i in 0..size {
+                            let child = children.next().unwrap();
+                            child.borrow_mut().parent = Some($ptr::downgrade(&node));
+                            node_ref.children[i] = Some(child);
+                            if i + 1 < size {
+                                let element = child_separators.next().unwrap();
+                                node_ref.keys[i] = Some(Self::clone_key(&element.borrow().key));
+                                node_ref.elements[i] = Some(element);
+                            }
+                        }
+                        node_ref.size = size - 1;
+                        node_ref.update_counts();
+                    }
+                    nodes.push(node);
+                    if let Some(separator) = child_separators.next() { separators.push(separator); }
+                }
+            }
+            nodes.pop().unwrap()
+        }
+
+        // Splits count into groups of max, evening out the last two groups when the
+        // last one would fall below min.
+        fn group_sizes(count: usize, max: usize, min: usize) -> Vec<usize> {
+            let groups = count.div_ceil(max);
+            let mut sizes = vec![max; groups - 1];
+            sizes.push(count - (groups - 1) * max);
+            if groups > 1 && sizes[groups - 1] < min {
+                let total = max + sizes[groups - 1];
+                sizes[groups - 2] = total / 2;
+                sizes[groups - 1] = total - total / 2;
+            }
+            sizes
+        }
+    };
+}
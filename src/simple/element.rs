@@ -8,56 +8,96 @@ use bionet_common::{
     distances::Distance
 };
 
+// The elements on either side of a key, as found by a failed search or left behind by unlink.
+pub(crate) type Neighbours<Key, const ORDER: usize, Value> = (
+    Option<Rc<RefCell<Element<Key, ORDER, Value>>>>, Option<Rc<RefCell<Element<Key, ORDER, Value>>>>
+);
+
 #[derive(Clone, Debug)]
-pub struct Element<Key, const ORDER: usize>
+pub struct Element<Key, const ORDER: usize, Value = ()>
 where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]: {
     pub key: Key,
-    pub counter: usize,
-    pub(crate) next: Option<Weak<RefCell<Element<Key, ORDER>>>>,
-    pub(crate) prev: Option<Weak<RefCell<Element<Key, ORDER>>>>
+    pub(crate) counter: usize,
+    pub(crate) weighted_counter: f32,
+    pub(crate) value: Value,
+    pub(crate) next: Option<Weak<RefCell<Element<Key, ORDER, Value>>>>,
+    pub(crate) prev: Option<Weak<RefCell<Element<Key, ORDER, Value>>>>
 }
 
-impl<Key, const ORDER: usize> Element<Key, ORDER> 
-where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:  {
+impl<Key, const ORDER: usize, Value> Element<Key, ORDER, Value> 
+where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]: {
     pub fn new(key: &Key)
-    -> Element<Key, ORDER> {
+    -> Element<Key, ORDER, Value>
+    where Value: Default {
+        Self::with_value(key, Value::default())
+    }
+
+    pub fn with_value(key: &Key, value: Value)
+    -> Element<Key, ORDER, Value> {
         Element {
             key: key.clone(),
             next: None,
             prev: None,
-            counter: 1
+            counter: 1,
+            weighted_counter: 1.0f32,
+            value
         }
     }
 
+    pub fn counter(&self) -> usize { self.counter }
+
+    pub fn weighted_counter(&self) -> f32 { self.weighted_counter }
+
+    pub fn value(&self) -> &Value { &self.value }
+
     pub fn set_connections(
-        element_ptr: &Rc<RefCell<Element<Key, ORDER>>>,
-        prev_opt: Option<&Rc<RefCell<Element<Key, ORDER>>>>,
-        next_opt: Option<&Rc<RefCell<Element<Key, ORDER>>>>
+        element_ptr: &Rc<RefCell<Element<Key, ORDER, Value>>>,
+        prev_opt: Option<&Rc<RefCell<Element<Key, ORDER, Value>>>>,
+        next_opt: Option<&Rc<RefCell<Element<Key, ORDER, Value>>>>
     ) {
         let mut element = element_ptr.borrow_mut();
         
-        if prev_opt.is_some() {
-            let prev_ptr = prev_opt.unwrap();
+        if let Some(prev_ptr) = prev_opt {
             element.prev = Some(Rc::downgrade(prev_ptr));
             prev_ptr.borrow_mut().next = Some(Rc::downgrade(element_ptr));
         } else { 
             element.prev = None; 
         }
 
-        if next_opt.is_some() {
-            let next_ptr = next_opt.unwrap();
+        if let Some(next_ptr) = next_opt {
             element.next = Some(Rc::downgrade(next_ptr));
-            next_ptr.borrow_mut().prev = Some(Rc::downgrade(&element_ptr));
+            next_ptr.borrow_mut().prev = Some(Rc::downgrade(element_ptr));
         } else { 
             element.next = None; 
         }
     }
+
+    pub(crate) fn unlink(element_ptr: &Rc<RefCell<Element<Key, ORDER, Value>>>) -> Neighbours<Key, ORDER, Value> {
+        let (prev_opt, next_opt) = {
+            let mut element = element_ptr.borrow_mut();
+            let prev_opt = element.prev.take().and_then(|prev| prev.upgrade());
+            let next_opt = element.next.take().and_then(|next| next.upgrade());
+            (prev_opt, next_opt)
+        };
+
+        match (&prev_opt, &next_opt) {
+            (Some(prev_ptr), Some(next_ptr)) => {
+                prev_ptr.borrow_mut().next = Some(Rc::downgrade(next_ptr));
+                next_ptr.borrow_mut().prev = Some(Rc::downgrade(prev_ptr));
+            },
+            (Some(prev_ptr), None) => prev_ptr.borrow_mut().next = None,
+            (None, Some(next_ptr)) => next_ptr.borrow_mut().prev = None,
+            (None, None) => {}
+        }
+
+        (prev_opt, next_opt)
+    }
 }
 
-impl<Key, const ORDER: usize> Display for Element<Key, ORDER> 
+impl<Key, const ORDER: usize, Value> Display for Element<Key, ORDER, Value> 
 where Key: Clone + Display + Distance + PartialOrd + PartialEq, [(); ORDER + 1]: {
     fn fmt(&self, f: &mut Formatter) -> Result {
-        write!(f, "[{}:{}]", &self.key, &self.counter)
+        write!(f, "[{}:{}]", self.key, self.counter)
     }
 }
 
@@ -1,36 +1,43 @@
 use std::{
     fmt::Display,
     rc::Rc,
-    cell::RefCell
+    cell::RefCell,
+    cmp::Ordering::*,
+    ops::{ RangeBounds, Bound::{ self, * } }
 };
 
 use bionet_common::distances::Distance;
 
 use super::{
-    element::Element,
-    node::Node
+    element::{ Element, Neighbours },
+    node::Node,
+    entry::{ Entry, OccupiedEntry, VacantEntry },
+    cursor::{ Cursor, CursorMut }
 };
 
 #[derive(Clone, Debug)]
-pub struct ASAGraph<Key, const ORDER: usize = 25>
+pub struct ASAGraph<Key, const ORDER: usize = 25, Value = ()>
 where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]: {
     pub name: String,
-    pub root: Rc<RefCell<Node<Key, ORDER>>>,
-    pub(crate) element_min: Option<Rc<RefCell<Element<Key, ORDER>>>>,
-    pub(crate) element_max: Option<Rc<RefCell<Element<Key, ORDER>>>>,
+    pub root: Rc<RefCell<Node<Key, ORDER, Value>>>,
+    pub(crate) element_min: Option<Rc<RefCell<Element<Key, ORDER, Value>>>>,
+    pub(crate) element_max: Option<Rc<RefCell<Element<Key, ORDER, Value>>>>,
     pub key_min: Option<Key>,
     pub key_max: Option<Key>
 }
 
-impl<Key, const ORDER: usize> ASAGraph<Key, ORDER> 
+// The element holding the searched key, or the elements on either side of it.
+type SearchResult<Key, const ORDER: usize, Value> = Result<Rc<RefCell<Element<Key, ORDER, Value>>>, Neighbours<Key, ORDER, Value>>;
+
+impl<Key, const ORDER: usize, Value> ASAGraph<Key, ORDER, Value> 
 where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]: {
-    pub fn new(name: &str) -> ASAGraph<Key, ORDER> {
+    pub fn new(name: &str) -> ASAGraph<Key, ORDER, Value> {
         if ORDER < 3 {
             panic!("Graph order must be >= 3");
         }
         ASAGraph {
             name: name.to_string(),
-            root: Rc::new(RefCell::new(Node::<Key, ORDER>::new(true, None))),
+            root: Rc::new(RefCell::new(Node::<Key, ORDER, Value>::new(true, None))),
             element_min: None,
             element_max: None,
             key_min: None,
@@ -38,21 +45,54 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
         }
     }
 
-    pub fn search(&self, key: &Key) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+    /// Sorts the keys and counts duplicates into single elements before building the tree bottom-up.
+    /// Keys that are not equal to themselves, such as `f64::NAN`, have no place in the order and are skipped.
+    pub fn new_bulk(name: &str, data: &[Key]) -> Self
+    where Value: Default {
+        let mut graph = Self::new(name);
+        let mut keys: Vec<Key> = data.iter().filter(|key| key.partial_cmp(key) == Some(Equal)).cloned().collect();
+        keys.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Equal));
+
+        let mut elements: Vec<Rc<RefCell<Element<Key, ORDER, Value>>>> = Vec::new();
+        for key in &keys {
+            let is_duplicate = match elements.last() {
+                Some(last) => last.borrow().key == *key,
+                None => false
+            };
+            if is_duplicate {
+                elements.last().unwrap().borrow_mut().counter += 1;
+                elements.last().unwrap().borrow_mut().weighted_counter += 1.0f32;
+            } else {
+                elements.push(Rc::new(RefCell::new(Element::new(key))));
+            }
+        }
+        graph.rebuild(elements);
+        graph
+    }
+
+    pub fn search(&self, key: &Key) -> Option<Rc<RefCell<Element<Key, ORDER, Value>>>> {
+        self.search_neighbours(key).ok()
+    }
+
+    fn search_neighbours(&self, key: &Key) -> SearchResult<Key, ORDER, Value> {
         let node = &self.root;
         
-        let (key_min, key_max) = self.extreme_keys()?;
+        let (key_min, key_max) = match self.extreme_keys() {
+            Some(extreme_keys) => extreme_keys,
+            None => return Err((None, None))
+        };
 
         if key.distance(key_max) > key.distance(key_min) {
-            return Self::search_left(key, &*node.borrow())
+            Self::search_left(key, &*node.borrow())
         } else {
-            return Self::search_right(key, &*node.borrow())
+            Self::search_right(key, &*node.borrow())
         }
     }
 
-    fn search_left<'a, 'b>(
-        key: &'a Key, mut node: &'b Node<Key, ORDER>
-    ) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+    fn search_left(
+        key: &Key, mut node: &Node<Key, ORDER, Value>
+    ) -> SearchResult<Key, ORDER, Value> {
+        let (mut prev, mut next) = (None, None);
         loop {
             let mut index = 0;
             {
@@ -67,10 +107,11 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
 
                 if index < node.size && key == current_key {
                     let element = node.elements[index].as_ref().unwrap().clone();
-                    return Some(element)
-                } else if node.is_leaf {
-                    return None
+                    return Ok(element)
                 }
+                if index > 0 { prev = node.elements[index - 1].clone(); }
+                if index < node.size { next = node.elements[index].clone(); }
+                if node.is_leaf { return Err((prev, next)) }
             }
                 
             let node_ptr = node.children[index].as_ref().unwrap();
@@ -78,9 +119,10 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
         }
     }
 
-    fn search_right<'a, 'b>(
-        key: &'a Key, mut node: &'b Node<Key, ORDER>
-    ) -> Option<Rc<RefCell<Element<Key, ORDER>>>> {
+    fn search_right(
+        key: &Key, mut node: &Node<Key, ORDER, Value>
+    ) -> SearchResult<Key, ORDER, Value> {
+        let (mut prev, mut next) = (None, None);
         loop {
             let mut index = node.size - 1;
             {
@@ -93,24 +135,142 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
 
                 if key == current_key {
                     let element = node.elements[index].as_ref().unwrap().clone();
-                    return Some(element)
-                } else if node.is_leaf {
-                    return None
+                    return Ok(element)
                 } else if key > current_key {
+                    prev = node.elements[index].clone();
                     index += 1;
+                    if index < node.size { next = node.elements[index].clone(); }
+                } else {
+                    next = node.elements[index].clone();
                 }
+                if node.is_leaf { return Err((prev, next)) }
             }
             let node_ptr = node.children[index].as_ref().unwrap();
             unsafe { node = node_ptr.try_borrow_unguarded().unwrap() };
         }
     }
 
-    pub fn insert(&mut self, key: &Key) -> Rc<RefCell<Element<Key, ORDER>>> {
+    pub fn floor(&self, key: &Key) -> Option<Rc<RefCell<Element<Key, ORDER, Value>>>> {
+        match self.search_neighbours(key) {
+            Ok(element) => Some(element),
+            Err((prev, _)) => prev
+        }
+    }
+
+    pub fn ceiling(&self, key: &Key) -> Option<Rc<RefCell<Element<Key, ORDER, Value>>>> {
+        match self.search_neighbours(key) {
+            Ok(element) => Some(element),
+            Err((_, next)) => next
+        }
+    }
+
+    pub fn predecessor(&self, key: &Key) -> Option<Rc<RefCell<Element<Key, ORDER, Value>>>> {
+        match self.search_neighbours(key) {
+            Ok(element) => element.borrow().prev.as_ref().and_then(|prev| prev.upgrade()),
+            Err((prev, _)) => prev
+        }
+    }
+
+    pub fn successor(&self, key: &Key) -> Option<Rc<RefCell<Element<Key, ORDER, Value>>>> {
+        match self.search_neighbours(key) {
+            Ok(element) => element.borrow().next.as_ref().and_then(|next| next.upgrade()),
+            Err((_, next)) => next
+        }
+    }
+
+    pub fn nearest(&self, key: &Key, k: usize) -> Vec<Rc<RefCell<Element<Key, ORDER, Value>>>> {
+        self.expand_by_distance(key, |count, _| count < k)
+    }
+
+    pub fn within(&self, key: &Key, radius: f64) -> Vec<Rc<RefCell<Element<Key, ORDER, Value>>>> {
+        self.expand_by_distance(key, |_, distance| distance <= radius)
+    }
+
+    fn expand_by_distance<F>(&self, key: &Key, mut is_accepted: F) -> Vec<Rc<RefCell<Element<Key, ORDER, Value>>>>
+    where F: FnMut(usize, f64) -> bool {
+        let mut result = Vec::new();
+        let mut left = self.floor(key);
+        let mut right = self.successor(key);
+        loop {
+            let left_distance = left.as_ref().map(|element| key.distance(&element.borrow().key).abs());
+            let right_distance = right.as_ref().map(|element| key.distance(&element.borrow().key).abs());
+            let (is_left, distance) = match (left_distance, right_distance) {
+                (Some(left_distance), Some(right_distance)) if left_distance <= right_distance => (true, left_distance),
+                (Some(_), Some(right_distance)) => (false, right_distance),
+                (Some(left_distance), None) => (true, left_distance),
+                (None, Some(right_distance)) => (false, right_distance),
+                (None, None) => return result
+            };
+            if !is_accepted(result.len(), distance) { return result }
+
+            if is_left {
+                let element = left.take().unwrap();
+                left = element.borrow().prev.as_ref().and_then(|prev| prev.upgrade());
+                result.push(element);
+            } else {
+                let element = right.take().unwrap();
+                right = element.borrow().next.as_ref().and_then(|next| next.upgrade());
+                result.push(element);
+            }
+        }
+    }
+
+    pub fn iter(&self) -> ASAGraphIntoIterator<'_, Key, ORDER, Value> { self.into_iter() }
+
+    pub fn range<R: RangeBounds<Key>>(&self, range: R) -> ASAGraphRangeIterator<'_, Key, ORDER, Value> {
+        let front = self.lower_bound_element(range.start_bound());
+        let back = self.upper_bound_element(range.end_bound());
+        let is_empty = match (&front, &back) {
+            (Some(front), Some(back)) => front.borrow().key > back.borrow().key,
+            _ => true
+        };
+
+        ASAGraphRangeIterator {
+            graph: self,
+            front: if is_empty { None } else { front },
+            back: if is_empty { None } else { back }
+        }
+    }
+
+    fn lower_bound_element(&self, bound: Bound<&Key>) -> Option<Rc<RefCell<Element<Key, ORDER, Value>>>> {
+        match bound {
+            Included(key) => self.ceiling(key),
+            Excluded(key) => self.successor(key),
+            Unbounded => self.element_min.clone()
+        }
+    }
+
+    fn upper_bound_element(&self, bound: Bound<&Key>) -> Option<Rc<RefCell<Element<Key, ORDER, Value>>>> {
+        match bound {
+            Included(key) => self.floor(key),
+            Excluded(key) => self.predecessor(key),
+            Unbounded => self.element_max.clone()
+        }
+    }
+
+    pub fn insert(&mut self, key: &Key) -> Rc<RefCell<Element<Key, ORDER, Value>>>
+    where Value: Default {
+        self.insert_into_node(key, Value::default()).1
+    }
+
+    // value is only used when key is not in the graph yet.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn insert_into_node(
+        &mut self, key: &Key, value: Value
+    ) -> (Rc<RefCell<Node<Key, ORDER, Value>>>, Rc<RefCell<Element<Key, ORDER, Value>>>) {
         let mut node = self.root.clone();
 
-        if node.borrow().size == 0 { return self.insert_first_element(&node, key) }
+        if node.borrow().size == Node::<Key, ORDER, Value>::MAX_KEYS { node = self.split_root(); }
 
-        if node.borrow().size == Node::<Key, ORDER>::MAX_KEYS { node = self.split_root(); }
+        self.insert_below(node, key, value)
+    }
+
+    // Descends from a node that is not full, splitting full children on the way down.
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn insert_below(
+        &mut self, mut node: Rc<RefCell<Node<Key, ORDER, Value>>>, key: &Key, value: Value
+    ) -> (Rc<RefCell<Node<Key, ORDER, Value>>>, Rc<RefCell<Element<Key, ORDER, Value>>>) {
+        if node.borrow().size == 0 { return (node.clone(), self.insert_first_element(&node, key, value)) }
 
         let (key_min, key_max) = self.extreme_keys().unwrap_or_else(|| {
             panic!("element_min / element_min must not be nullptr")
@@ -122,21 +282,24 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
             } else {
                 node.borrow().insert_existing_key(key, false)
             };
-            if let Some(el) = node_insert_result.0 { return el }
+            if let Some(el) = node_insert_result.0 {
+                Node::update_counts_upwards(&node);
+                return (node, el)
+            }
             let mut index = node_insert_result.1;
     
             if node.borrow().is_leaf {
-                let element = Node::insert_key_leaf(&node, key);
+                let element = Node::insert_key_leaf_with(&node, key, value);
                 self.set_extrema(&element);
-                return element
+                return (node, element)
             } else {
                 let child_size = node.borrow().children[index].as_ref().unwrap().borrow().size;
-                if child_size == Node::<Key, ORDER>::MAX_KEYS {
+                if child_size == Node::<Key, ORDER, Value>::MAX_KEYS {
                     Node::split_child(&node, index);
                     if key > &node.borrow().elements[index].as_ref().unwrap().borrow().key {
                         index += 1 
                     } else if key == node.borrow().keys[index].as_ref().unwrap() {
-                        return node.borrow().elements[index].as_ref().unwrap().clone()
+                        continue
                     }
                 }
                 let new_node = node.borrow().children[index].as_ref().unwrap().clone();
@@ -145,11 +308,301 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
         }
     }
 
+    /// Returns `None` when `n` is 0 and `key` is not in the graph.
+    pub fn insert_n(&mut self, key: &Key, n: usize) -> Option<Rc<RefCell<Element<Key, ORDER, Value>>>>
+    where Value: Default {
+        self.insert_counted(key, n, n as f32)
+    }
+
+    pub fn insert_weighted(&mut self, key: &Key, weight: f32) -> Rc<RefCell<Element<Key, ORDER, Value>>>
+    where Value: Default {
+        self.insert_counted(key, 1, weight).unwrap()
+    }
+
+    fn insert_counted(
+        &mut self, key: &Key, counter: usize, weighted_counter: f32
+    ) -> Option<Rc<RefCell<Element<Key, ORDER, Value>>>>
+    where Value: Default {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => {
+                entry.add(counter, weighted_counter);
+                Some(entry.element())
+            },
+            Entry::Vacant(entry) => entry.insert_weighted(counter, weighted_counter)
+        }
+    }
+
+    pub fn entry(&mut self, key: &Key) -> Entry<'_, Key, ORDER, Value> {
+        match Node::find_node_or_leaf(&self.root, key) {
+            Ok((node, index)) => {
+                let element = node.borrow().elements[index].as_ref().unwrap().clone();
+                Entry::Occupied(OccupiedEntry { graph: self, node, element })
+            },
+            Err(leaf) => Entry::Vacant(VacantEntry { graph: self, leaf, key: key.clone() })
+        }
+    }
+
+    pub fn cursor(&self) -> Cursor<'_, Key, ORDER, Value> {
+        Cursor { graph: self, current: self.element_min.clone() }
+    }
+
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, Key, ORDER, Value> {
+        let current = self.element_min.clone();
+        CursorMut { graph: self, current }
+    }
+
+    pub fn merge(&mut self, other: &Self)
+    where Value: Clone {
+        self.merge_with(other, |_, _| {})
+    }
+
+    pub fn merge_with<F>(&mut self, other: &Self, mut merge_values: F)
+    where F: FnMut(&mut Value, &Value), Value: Clone {
+        // other may share elements with self through the derived Clone,
+        // so its counters are copied out before any element is borrowed mutably
+        let other_elements: Vec<(Key, usize, f32, Value)> = other.iter()
+            .map(|element| {
+                let element = element.borrow();
+                (element.key.clone(), element.counter, element.weighted_counter, element.value.clone())
+            })
+            .collect();
+        let mut elements = Vec::with_capacity(self.count_elements_unique() + other_elements.len());
+        {
+            let mut self_iter = self.iter().peekable();
+            let mut other_iter = other_elements.into_iter().peekable();
+            loop {
+                let ordering = match (self_iter.peek(), other_iter.peek()) {
+                    (Some(element), Some(other_element)) => {
+                        element.borrow().key.partial_cmp(&other_element.0)
+                    },
+                    (Some(_), None) => Some(Less),
+                    (None, Some(_)) => Some(Greater),
+                    (None, None) => break
+                };
+                match ordering {
+                    Some(Less) => elements.push(self_iter.next().unwrap()),
+                    Some(Greater) => {
+                        let (key, counter, weighted_counter, value) = other_iter.next().unwrap();
+                        let element = Rc::new(RefCell::new(Element::with_value(&key, value)));
+                        element.borrow_mut().counter = counter;
+                        element.borrow_mut().weighted_counter = weighted_counter;
+                        elements.push(element);
+                    },
+                    _ => {
+                        let element = self_iter.next().unwrap();
+                        let (_, counter, weighted_counter, value) = other_iter.next().unwrap();
+                        element.borrow_mut().counter += counter;
+                        element.borrow_mut().weighted_counter += weighted_counter;
+                        merge_values(&mut element.borrow_mut().value, &value);
+                        elements.push(element);
+                    }
+                }
+            }
+        }
+        self.rebuild(elements);
+    }
+
+    /// Keeps this graph's payloads; use `intersection_with` to combine them.
+    pub fn intersection(&self, other: &Self, semantics: CounterSemantics) -> Self
+    where Value: Clone {
+        self.intersection_with(other, semantics, |_, _| {})
+    }
+
+    pub fn intersection_with<F>(&self, other: &Self, semantics: CounterSemantics, merge_values: F) -> Self
+    where F: FnMut(&mut Value, &Value), Value: Clone {
+        self.combine(other, |counter, other_counter| match (counter, other_counter) {
+            (Some(counter), Some(other_counter)) => Some(semantics.apply(counter, other_counter)),
+            _ => None
+        }, merge_values)
+    }
+
+    /// Multiset difference: each key keeps its counter minus the other graph's counter
+    /// and is left out once that reaches zero. Weighted counters shrink in proportion.
+    /// Payloads are this graph's.
+    pub fn difference(&self, other: &Self) -> Self
+    where Value: Clone {
+        self.combine(other, |counter, other_counter| match (counter, other_counter) {
+            (Some(counter), None) => Some(counter),
+            (Some(counter), Some(other_counter)) => subtract_counters(counter, other_counter),
+            _ => None
+        }, |_, _| {})
+    }
+
+    /// Multiset symmetric difference: a key in both graphs keeps the absolute
+    /// difference of its counters, taken from the graph with the larger counter.
+    /// Such a key keeps this graph's payload.
+    pub fn symmetric_difference(&self, other: &Self) -> Self
+    where Value: Clone {
+        self.combine(other, |counter, other_counter| match (counter, other_counter) {
+            (Some(counter), None) | (None, Some(counter)) => Some(counter),
+            (Some(counter), Some(other_counter)) => subtract_counters(counter, other_counter)
+                .or_else(|| subtract_counters(other_counter, counter)),
+            _ => None
+        }, |_, _| {})
+    }
+
+    fn combine<F, M>(&self, other: &Self, mut combine_counters: F, mut merge_values: M) -> Self
+    where
+        F: FnMut(Option<(usize, f32)>, Option<(usize, f32)>) -> Option<(usize, f32)>,
+        M: FnMut(&mut Value, &Value),
+        Value: Clone
+    {
+        let mut graph = Self::new(&self.name);
+        let mut elements = Vec::new();
+        let mut self_iter = self.iter().peekable();
+        let mut other_iter = other.iter().peekable();
+        loop {
+            let ordering = match (self_iter.peek(), other_iter.peek()) {
+                (Some(element), Some(other_element)) => {
+                    element.borrow().key.partial_cmp(&other_element.borrow().key)
+                },
+                (Some(_), None) => Some(Less),
+                (None, Some(_)) => Some(Greater),
+                (None, None) => break
+            };
+            let (element, other_element, counter, other_counter) = match ordering {
+                Some(Less) => {
+                    let element = self_iter.next().unwrap();
+                    let counter = (element.borrow().counter, element.borrow().weighted_counter);
+                    (element, None, Some(counter), None)
+                },
+                Some(Greater) => {
+                    let element = other_iter.next().unwrap();
+                    let counter = (element.borrow().counter, element.borrow().weighted_counter);
+                    (element, None, None, Some(counter))
+                },
+                _ => {
+                    let element = self_iter.next().unwrap();
+                    let other_element = other_iter.next().unwrap();
+                    let counter = (element.borrow().counter, element.borrow().weighted_counter);
+                    let other_counter = (
+                        other_element.borrow().counter, other_element.borrow().weighted_counter
+                    );
+                    (element, Some(other_element), Some(counter), Some(other_counter))
+                }
+            };
+            if let Some((counter, weighted_counter)) = combine_counters(counter, other_counter) {
+                let key = element.borrow().key.clone();
+                let new_element = Rc::new(RefCell::new(Element::with_value(&key, element.borrow().value.clone())));
+                new_element.borrow_mut().counter = counter;
+                new_element.borrow_mut().weighted_counter = weighted_counter;
+                if let Some(other_element) = other_element {
+                    merge_values(&mut new_element.borrow_mut().value, &other_element.borrow().value);
+                }
+                elements.push(new_element);
+            }
+        }
+        graph.rebuild(elements);
+        graph
+    }
+
+    pub fn split_off(&mut self, key: &Key) -> Self {
+        let mut other = Self::new(&self.name);
+        if self.extreme_keys().is_none() { return other }
+
+        let root = (self.root.clone(), Node::height(&self.root));
+        let (left, right) = Node::split(&root.0, root.1, key, false);
+        self.root = left.0;
+        other.root = right.0;
+
+        let first = match Node::extreme_element(&other.root, false) {
+            Some(element) => element,
+            None => return other
+        };
+        first.borrow_mut().prev = None;
+        other.key_min = Some(first.borrow().key.clone());
+        other.element_min = Some(first);
+        other.key_max = self.key_max.take();
+        other.element_max = self.element_max.take();
+
+        match Node::extreme_element(&self.root, true) {
+            Some(last) => {
+                last.borrow_mut().next = None;
+                self.key_max = Some(last.borrow().key.clone());
+                self.element_max = Some(last);
+            },
+            None => {
+                self.key_min = None;
+                self.element_min = None;
+            }
+        }
+
+        other
+    }
+
+    pub fn remove(&mut self, key: &Key) -> Option<Rc<RefCell<Element<Key, ORDER, Value>>>> {
+        let element = self.search(key)?;
+
+        Node::remove_key(&self.root, key);
+        self.shrink_root();
+
+        let (prev_opt, next_opt) = Element::unlink(&element);
+        self.unset_extrema(&element, prev_opt.as_ref(), next_opt.as_ref());
+
+        Some(element)
+    }
+
+    pub fn remove_range<R: RangeBounds<Key>>(&mut self, range: R) -> usize {
+        if self.extreme_keys().is_none() { return 0 }
+
+        let root = (self.root.clone(), Node::height(&self.root));
+        let (left, rest) = match range.start_bound() {
+            Included(key) => Node::split(&root.0, root.1, key, false),
+            Excluded(key) => Node::split(&root.0, root.1, key, true),
+            Unbounded => (Self::empty_tree(), root)
+        };
+        let (middle, right) = match range.end_bound() {
+            Included(key) => Node::split(&rest.0, rest.1, key, true),
+            Excluded(key) => Node::split(&rest.0, rest.1, key, false),
+            Unbounded => (rest, Self::empty_tree())
+        };
+        self.root = Node::concat(left, right).0;
+
+        let first = match Node::extreme_element(&middle.0, false) {
+            Some(element) => element,
+            None => return 0
+        };
+        let last = Node::extreme_element(&middle.0, true).unwrap();
+        let prev_opt = first.borrow_mut().prev.take().map(|prev| prev.upgrade().unwrap());
+        let next_opt = last.borrow_mut().next.take().map(|next| next.upgrade().unwrap());
+
+        let mut counter = 0usize;
+        let mut element = first;
+        loop {
+            counter += element.borrow().counter;
+            element.borrow_mut().prev = None;
+            let next_element = match element.borrow_mut().next.take() {
+                Some(next) => next.upgrade().unwrap(),
+                None => break
+            };
+            element = next_element;
+        }
+
+        match (&prev_opt, &next_opt) {
+            (Some(prev), Some(next)) => {
+                prev.borrow_mut().next = Some(Rc::downgrade(next));
+                next.borrow_mut().prev = Some(Rc::downgrade(prev));
+            },
+            (Some(prev), None) => prev.borrow_mut().next = None,
+            (None, Some(next)) => next.borrow_mut().prev = None,
+            (None, None) => {}
+        }
+        if prev_opt.is_none() {
+            self.element_min = next_opt.clone();
+            self.key_min = next_opt.as_ref().map(|next| next.borrow().key.clone());
+        }
+        if next_opt.is_none() {
+            self.element_max = prev_opt.clone();
+            self.key_max = prev_opt.as_ref().map(|prev| prev.borrow().key.clone());
+        }
+
+        counter
+    }
+
     pub fn print_graph(&self) {
         let mut height = 0;
         let mut node = self.root.clone();
-        let mut queue: Vec<Vec<Rc<RefCell<Node<Key, ORDER>>>>> = vec![vec![]];
-        queue[0].push(node.clone());
+        let mut queue = vec![vec![node.clone()]];
 
         loop {
             queue.push(vec![]);
@@ -159,7 +612,7 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
                 print!("||");
                 for j in 0..(node_size) {
                     let element = node.borrow().elements[j].as_ref().unwrap().clone();
-                    print!("{}:{}|", &element.borrow().key, element.borrow().counter);
+                    print!("{}:{}|", element.borrow().key, element.borrow().counter);
                     if !node.borrow().is_leaf {
                         queue[height + 1].push(node.borrow().children[j].as_ref().unwrap().clone());
                     }
@@ -169,17 +622,17 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
                 }
                 print!("| ");
             }
-            if queue.last().unwrap().len() > 0 {
+            if !queue.last().unwrap().is_empty() {
                 height += 1;
-                println!("");
+                println!();
             } else {
-                println!("");
+                println!();
                 return
             }
         }
     }
 
-    fn extreme_keys<'a>(&'a self) -> Option<(&'a Key, &'a Key)> {
+    fn extreme_keys(&self) -> Option<(&Key, &Key)> {
         if self.key_min.is_none() || self.key_max.is_none() { return None }
         let key_min =  self.key_min.as_ref().unwrap();
         let key_max =  self.key_max.as_ref().unwrap();
@@ -187,9 +640,9 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
     }
 
     fn insert_first_element(
-        &mut self, node: &Rc<RefCell<Node<Key, ORDER>>>,  key: &Key
-    ) -> Rc<RefCell<Element<Key, ORDER>>> {
-        let element_pointer = Rc::new(RefCell::new(Element::<Key, ORDER>::new(key)));
+        &mut self, node: &Rc<RefCell<Node<Key, ORDER, Value>>>,  key: &Key, value: Value
+    ) -> Rc<RefCell<Element<Key, ORDER, Value>>> {
+        let element_pointer = Rc::new(RefCell::new(Element::<Key, ORDER, Value>::with_value(key, value)));
         node.borrow_mut().elements[0] = Some(element_pointer.clone());
         node.borrow_mut().keys[0] = Some(key.clone());
 
@@ -198,11 +651,49 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
         self.element_min = Some(element_pointer.clone());
         self.element_max = Some(element_pointer.clone());
         node.borrow_mut().size = 1;
+        node.borrow_mut().update_counts();
 
         element_pointer
     }
 
-    fn split_root(&mut self) -> Rc<RefCell<Node<Key, ORDER>>> {
+    fn rebuild(&mut self, elements: Vec<Rc<RefCell<Element<Key, ORDER, Value>>>>) {
+        self.root = Node::build(&elements);
+
+        let (first, last) = match (elements.first(), elements.last()) {
+            (Some(first), Some(last)) => (first.clone(), last.clone()),
+            _ => {
+                self.element_min = None;
+                self.element_max = None;
+                self.key_min = None;
+                self.key_max = None;
+                return
+            }
+        };
+        first.borrow_mut().prev = None;
+        last.borrow_mut().next = None;
+        self.key_min = Some(first.borrow().key.clone());
+        self.key_max = Some(last.borrow().key.clone());
+        self.element_min = Some(first);
+        self.element_max = Some(last);
+
+        for pair in elements.windows(2) {
+            pair[0].borrow_mut().next = Some(Rc::downgrade(&pair[1]));
+            pair[1].borrow_mut().prev = Some(Rc::downgrade(&pair[0]));
+        }
+    }
+
+    fn empty_tree() -> (Rc<RefCell<Node<Key, ORDER, Value>>>, usize) {
+        (Rc::new(RefCell::new(Node::new(true, None))), 0)
+    }
+
+    fn shrink_root(&mut self) {
+        if self.root.borrow().size > 0 || self.root.borrow().is_leaf { return }
+        let new_root = self.root.borrow_mut().children[0].take().unwrap();
+        new_root.borrow_mut().parent = None;
+        self.root = new_root;
+    }
+
+    fn split_root(&mut self) -> Rc<RefCell<Node<Key, ORDER, Value>>> {
         let new_root = Rc::new(RefCell::new(Node::new(false, None)));
         let old_root = self.root.clone();
         self.root = new_root;
@@ -212,7 +703,7 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
         self.root.clone()
     }
 
-    fn set_extrema(&mut self, element: &Rc<RefCell<Element<Key, ORDER>>>) {
+    fn set_extrema(&mut self, element: &Rc<RefCell<Element<Key, ORDER, Value>>>) {
         let key = &element.borrow().key;
         let key_min = &self.key_min;
         let key_max = &self.key_max;
@@ -235,45 +726,233 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
         }
     }
 
-    pub fn count_elements_unique(&self) -> usize {
-        let mut counter = 0usize;
-        let mut element = match &self.element_min {
-            Some(e) => e.clone(),
-            None => return counter
-        };
+    fn unset_extrema(
+        &mut self,
+        element: &Rc<RefCell<Element<Key, ORDER, Value>>>,
+        prev_opt: Option<&Rc<RefCell<Element<Key, ORDER, Value>>>>,
+        next_opt: Option<&Rc<RefCell<Element<Key, ORDER, Value>>>>
+    ) {
+        if let Some(element_min) = &self.element_min {
+            if Rc::ptr_eq(element_min, element) {
+                self.element_min = next_opt.cloned();
+                self.key_min = next_opt.map(|next| next.borrow().key.clone());
+            }
+        }
+        if let Some(element_max) = &self.element_max {
+            if Rc::ptr_eq(element_max, element) {
+                self.element_max = prev_opt.cloned();
+                self.key_max = prev_opt.map(|prev| prev.borrow().key.clone());
+            }
+        }
+    }
+
+    pub fn count_elements_unique(&self) -> usize { self.root.borrow().count_unique }
+
+    pub fn count_elements_agg(&self) -> usize { self.root.borrow().count_agg }
+
+    pub fn count_elements_weighted(&self) -> f32 { self.root.borrow().weighted_count_agg }
+
+    pub fn rank(&self, key: &Key) -> usize { self.count_below(key, false).0 }
+
+    pub fn select(&self, index: usize) -> Option<Rc<RefCell<Element<Key, ORDER, Value>>>> {
+        let mut index = index;
+        let mut node = self.root.clone();
         loop {
-            counter += 1;
-            let new_element= match &element.borrow().next {
-                Some(e) => e.upgrade().unwrap().clone(),
-                None => return counter
+            let child = {
+                let node_ref = node.borrow();
+                let mut child_index = node_ref.size;
+                for i in 0..node_ref.size {
+                    if !node_ref.is_leaf {
+                        let child_count = node_ref.children[i].as_ref().unwrap().borrow().count_unique;
+                        if index < child_count {
+                            child_index = i;
+                            break
+                        }
+                        index -= child_count;
+                    }
+                    if index == 0 { return Some(node_ref.elements[i].as_ref().unwrap().clone()) }
+                    index -= 1;
+                }
+                if node_ref.is_leaf { return None }
+                node_ref.children[child_index].as_ref().unwrap().clone()
             };
-            element = new_element;
+            node = child;
         }
     }
 
-    pub fn count_elements_agg(&self) -> usize {
-        let mut counter = 0usize;
-        let mut element = match &self.element_min {
-            Some(e) => e.clone(),
-            None => return counter
+    pub fn count_in_range<R: RangeBounds<Key>>(&self, range: R) -> usize {
+        let lower = match range.start_bound() {
+            Included(key) => self.count_below(key, false).0,
+            Excluded(key) => self.count_below(key, true).0,
+            Unbounded => 0
         };
+        let upper = match range.end_bound() {
+            Included(key) => self.count_below(key, true).0,
+            Excluded(key) => self.count_below(key, false).0,
+            Unbounded => self.count_elements_unique()
+        };
+        upper.saturating_sub(lower)
+    }
+
+    fn count_below(&self, key: &Key, include_key: bool) -> (usize, usize) {
+        let (mut count_unique, mut count_agg) = (0, 0);
+        let mut node = self.root.clone();
         loop {
-            counter += element.borrow().counter;
-            let new_element= match &element.borrow().next {
-                Some(e) => e.upgrade().unwrap().clone(),
-                None => return counter
+            let child = {
+                let node_ref = node.borrow();
+                let mut index = 0;
+                while index < node_ref.size {
+                    let current_key = node_ref.keys[index].as_ref().unwrap();
+                    if !(current_key < key || (include_key && current_key == key)) { break }
+                    if !node_ref.is_leaf {
+                        let child = node_ref.children[index].as_ref().unwrap().borrow();
+                        count_unique += child.count_unique;
+                        count_agg += child.count_agg;
+                    }
+                    count_unique += 1;
+                    count_agg += node_ref.elements[index].as_ref().unwrap().borrow().counter;
+                    index += 1;
+                }
+                if node_ref.is_leaf { return (count_unique, count_agg) }
+                node_ref.children[index].as_ref().unwrap().clone()
+            };
+            node = child;
+        }
+    }
+}
+
+/// How `intersection` combines the counters of a key present in both graphs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CounterSemantics {
+    Min,
+    Max,
+    Sum
+}
+
+impl CounterSemantics {
+    pub(crate) fn apply(self, counter: (usize, f32), other_counter: (usize, f32)) -> (usize, f32) {
+        match self {
+            CounterSemantics::Min if counter.0 <= other_counter.0 => counter,
+            CounterSemantics::Max if counter.0 >= other_counter.0 => counter,
+            CounterSemantics::Min | CounterSemantics::Max => other_counter,
+            CounterSemantics::Sum => (counter.0 + other_counter.0, counter.1 + other_counter.1)
+        }
+    }
+}
+
+pub(crate) fn subtract_counters(counter: (usize, f32), other_counter: (usize, f32)) -> Option<(usize, f32)> {
+    let remaining = counter.0.checked_sub(other_counter.0).filter(|remaining| *remaining > 0)?;
+    Some((remaining, counter.1 * remaining as f32 / counter.0 as f32))
+}
+
+impl<'a, Key, const ORDER: usize, Value> IntoIterator for &'a ASAGraph<Key, ORDER, Value> 
+where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]: {
+    type Item = Rc<RefCell<Element<Key, ORDER, Value>>>;
+    type IntoIter = ASAGraphIntoIterator<'a, Key, ORDER, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        ASAGraphIntoIterator {
+            graph: self,
+            front: self.element_min.clone(),
+            back: self.element_max.clone()
+        }
+    }
+}
+
+pub struct ASAGraphIntoIterator<'a, Key, const ORDER: usize = 25, Value = ()>
+where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]: {
+    graph: &'a ASAGraph<Key, ORDER, Value>,
+    front: Option<Rc<RefCell<Element<Key, ORDER, Value>>>>,
+    back: Option<Rc<RefCell<Element<Key, ORDER, Value>>>>
+}
+
+impl<'a, Key, const ORDER: usize, Value> Iterator for ASAGraphIntoIterator<'a, Key, ORDER, Value> 
+where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]: {
+    type Item = Rc<RefCell<Element<Key, ORDER, Value>>>;
+    fn next(&mut self) -> Option<Rc<RefCell<Element<Key, ORDER, Value>>>> {
+        let element = self.front.take()?;
+
+        if Rc::ptr_eq(&element, self.back.as_ref().unwrap()) {
+            self.back = None;
+        } else {
+            self.front = match &element.borrow().next {
+                Some(next) => next.upgrade(),
+                None => None
+            };
+        }
+
+        Some(element)
+    }
+}
+
+impl<'a, Key, const ORDER: usize, Value> DoubleEndedIterator for ASAGraphIntoIterator<'a, Key, ORDER, Value> 
+where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]: {
+    fn next_back(&mut self) -> Option<Rc<RefCell<Element<Key, ORDER, Value>>>> {
+        let element = self.back.take()?;
+
+        if Rc::ptr_eq(&element, self.front.as_ref().unwrap()) {
+            self.front = None;
+        } else {
+            self.back = match &element.borrow().prev {
+                Some(prev) => prev.upgrade(),
+                None => None
             };
-            element = new_element;
         }
+
+        Some(element)
+    }
+}
+
+pub struct ASAGraphRangeIterator<'a, Key, const ORDER: usize = 25, Value = ()>
+where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]: {
+    graph: &'a ASAGraph<Key, ORDER, Value>,
+    front: Option<Rc<RefCell<Element<Key, ORDER, Value>>>>,
+    back: Option<Rc<RefCell<Element<Key, ORDER, Value>>>>
+}
+
+impl<'a, Key, const ORDER: usize, Value> Iterator for ASAGraphRangeIterator<'a, Key, ORDER, Value> 
+where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]: {
+    type Item = Rc<RefCell<Element<Key, ORDER, Value>>>;
+    fn next(&mut self) -> Option<Rc<RefCell<Element<Key, ORDER, Value>>>> {
+        let element = self.front.take()?;
+
+        if Rc::ptr_eq(&element, self.back.as_ref().unwrap()) {
+            self.back = None;
+        } else {
+            self.front = match &element.borrow().next {
+                Some(next) => next.upgrade(),
+                None => None
+            };
+        }
+
+        Some(element)
+    }
+}
+
+impl<'a, Key, const ORDER: usize, Value> DoubleEndedIterator for ASAGraphRangeIterator<'a, Key, ORDER, Value> 
+where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]: {
+    fn next_back(&mut self) -> Option<Rc<RefCell<Element<Key, ORDER, Value>>>> {
+        let element = self.back.take()?;
+
+        if Rc::ptr_eq(&element, self.front.as_ref().unwrap()) {
+            self.front = None;
+        } else {
+            self.back = match &element.borrow().prev {
+                Some(prev) => prev.upgrade(),
+                None => None
+            };
+        }
+
+        Some(element)
     }
 }
 
 #[cfg(test)]
 pub mod tests {
     use rand::Rng;
-    use std::time::Instant;
+    use std::{ collections::BTreeMap, time::Instant, ops::Bound::Excluded };
     
-    use super::ASAGraph;
+    use super::{ ASAGraph, CounterSemantics, super::node::Node };
 
     #[test]
     fn create_empty_graph() {
@@ -447,4 +1126,37 @@ pub mod tests {
             current_element = prev_element.borrow().next.as_ref().unwrap().upgrade().unwrap().clone();
         }
     }
+
+    #[test]
+    fn remove() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 1..=50 { graph.insert(&i); }
+        graph.insert(&20);
+
+        assert_eq!(graph.remove(&20).unwrap().borrow().counter, 2);
+        assert!(graph.remove(&20).is_none());
+        let nineteen = graph.search(&19).unwrap();
+        assert_eq!(nineteen.borrow().next.as_ref().unwrap().upgrade().unwrap().borrow().key, 21);
+
+        let element = graph.remove(&1).unwrap();
+        assert!(element.borrow().prev.is_none() && element.borrow().next.is_none());
+        assert_eq!(graph.key_min, Some(2));
+        assert!(graph.element_min.as_ref().unwrap().borrow().prev.is_none());
+        graph.remove(&50);
+        assert_eq!(graph.key_max, Some(49));
+        assert!(graph.element_max.as_ref().unwrap().borrow().next.is_none());
+        assert_eq!(graph.validate(), Ok(()));
+
+        for i in 2..=49 { graph.remove(&i); }
+        assert_eq!(graph.count_elements_unique(), 0);
+        assert!(graph.key_min.is_none() && graph.element_max.is_none());
+        assert_eq!(graph.validate(), Ok(()));
+    }
+
+    graph_tests!(ASAGraph);
+
+    differential_tests!(ASAGraph, |graph: &mut ASAGraph<i32, ORDER>, model: &mut BTreeMap<i32, usize>, key: i32, _: bool| {
+        assert_eq!(graph.remove(&key).map(|e| e.borrow().counter), model.remove(&key), "remove {}", key);
+    });
+
 }
\ No newline at end of file
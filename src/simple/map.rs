@@ -0,0 +1,135 @@
+use std::{
+    fmt::Display,
+    rc::Rc,
+    cell::RefCell
+};
+
+use bionet_common::distances::Distance;
+
+use super::{
+    element::Element,
+    node::Node,
+    graph::ASAGraph,
+    entry::Entry
+};
+
+pub type ASAMap<Key, Value, const ORDER: usize = 25> = ASAGraph<Key, ORDER, Value>;
+
+impl<Key, const ORDER: usize, Value> ASAGraph<Key, ORDER, Value> 
+where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]: {
+    pub fn insert_with<F>(
+        &mut self, key: &Key, value: Value, merge_values: F
+    ) -> Rc<RefCell<Element<Key, ORDER, Value>>>
+    where F: FnOnce(&mut Value, &Value) {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => {
+                merge_values(&mut entry.element.borrow_mut().value, &value);
+                entry.add(1, 1.0f32);
+                entry.element()
+            },
+            Entry::Vacant(entry) => entry.insert_with_value(1, 1.0f32, value).unwrap()
+        }
+    }
+
+    pub fn get(&self, key: &Key) -> Option<Value>
+    where Value: Clone {
+        Some(self.search(key)?.borrow().value.clone())
+    }
+
+    /// Returns `false` when `key` is not in the map.
+    pub fn update_value<F>(&mut self, key: &Key, f: F) -> bool
+    where F: FnOnce(&mut Value) {
+        match self.entry(key) {
+            Entry::Occupied(entry) => {
+                f(&mut entry.element.borrow_mut().value);
+                // drops the frozen snapshot nodes on the path, which hold a copy of the payload
+                Node::update_counts_upwards(&entry.node);
+                true
+            },
+            Entry::Vacant(_) => false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ ASAMap, super::graph::CounterSemantics };
+
+    #[test]
+    fn insert_with() {
+        let mut map = ASAMap::<i32, Vec<usize>, 3>::new("test");
+        for (record_id, key) in [5, 3, 5, 8, 3, 5].iter().enumerate() {
+            map.insert_with(key, vec![record_id], |ids, new_ids| ids.extend(new_ids));
+        }
+
+        assert_eq!(map.get(&5), Some(vec![0, 2, 5]));
+        assert_eq!(map.get(&3), Some(vec![1, 4]));
+        assert_eq!(map.get(&8), Some(vec![3]));
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.search(&5).unwrap().borrow().counter, 3);
+        assert_eq!(map.count_elements_agg(), 6);
+
+        assert!(map.update_value(&8, |ids| ids.push(6)));
+        assert!(!map.update_value(&1, |ids| ids.push(7)));
+        assert_eq!(map.get(&8), Some(vec![3, 6]));
+        assert_eq!(map.search(&8).unwrap().borrow().value(), &vec![3, 6]);
+
+        let mut sums = ASAMap::<i32, f64, 3>::new("sums");
+        for i in 0..100 {
+            sums.insert_with(&(i % 10), i as f64, |sum, value| *sum += value);
+        }
+        assert_eq!(sums.get(&0), Some(450.0f64));
+        assert_eq!(sums.get(&9), Some(540.0f64));
+        let keys: Vec<i32> = sums.range(3..6).map(|e| e.borrow().key).collect();
+        assert_eq!(keys, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn merge_with() {
+        let mut map = ASAMap::<i32, Vec<usize>, 3>::new("test");
+        let mut other = ASAMap::<i32, Vec<usize>, 3>::new("other");
+        for i in 0..10 { map.insert_with(&i, vec![i as usize], |_, _| {}); }
+        for i in 5..15 { other.insert_with(&i, vec![100 + i as usize], |_, _| {}); }
+
+        map.merge_with(&other, |ids, other_ids| ids.extend(other_ids));
+        assert_eq!(map.count_elements_unique(), 15);
+        assert_eq!(map.get(&2), Some(vec![2]));
+        assert_eq!(map.get(&7), Some(vec![7, 107]));
+        assert_eq!(map.get(&12), Some(vec![112]));
+
+        let intersection = map.intersection(&other, CounterSemantics::Sum);
+        assert_eq!(intersection.get(&7), Some(vec![7, 107]));
+        let intersection = map.intersection_with(&other, CounterSemantics::Sum, |ids, other_ids| {
+            ids.retain(|id| !other_ids.contains(id))
+        });
+        assert_eq!(intersection.count_elements_unique(), 10);
+        assert_eq!(intersection.get(&7), Some(vec![7]));
+        assert_eq!(intersection.get(&12), Some(vec![]));
+
+        let difference = other.symmetric_difference(&map);
+        assert_eq!(difference.count_elements_unique(), 10);
+        assert_eq!(difference.get(&2), Some(vec![2]));
+        assert_eq!(difference.get(&7), Some(vec![107]));
+        assert!(difference.get(&12).is_none());
+    }
+
+    #[test]
+    fn payload_without_default_or_clone() {
+        struct Total(u64);
+
+        let mut map = ASAMap::<i32, Total, 3>::new("test");
+        for i in 0..50 { map.insert_with(&(i % 7), Total(i as u64), |total, value| total.0 += value.0); }
+        assert!(map.update_value(&3, |total| total.0 += 1000));
+
+        assert_eq!(map.count_elements_unique(), 7);
+        assert_eq!(map.search(&0).unwrap().borrow().value().0, 196);
+        assert_eq!(map.search(&3).unwrap().borrow().value().0, 1168);
+        let mut cursor = map.cursor_mut();
+        cursor.seek(&5);
+        assert_eq!(cursor.increment_counter(), Some(8));
+        assert_eq!(map.count_elements_agg(), 51);
+        assert_eq!(map.remove_range(0..3), 22);
+        assert_eq!(map.count_elements_unique(), 4);
+        map.validate().unwrap();
+    }
+}
@@ -1,3 +1,8 @@
 pub mod graph;
 pub mod node;
-pub mod element;
\ No newline at end of file
+pub mod element;
+pub mod entry;
+pub mod cursor;
+pub mod map;
+pub mod validation;
+pub mod snapshot;
\ No newline at end of file
@@ -1,7 +1,8 @@
 use std::{
     fmt::{ Display, Formatter, Result },
     rc::{ Rc, Weak },
-    cell::{ RefCell, Ref, RefMut }
+    cell::{ RefCell, Ref, RefMut },
+    cmp::{ Ordering, Ordering::* }
 };
 
 use bionet_common::{
@@ -9,36 +10,49 @@ use bionet_common::{
     algorithms::SearchAlgorithm
 };
 
-use super::element::Element;
+use super::{
+    element::Element,
+    snapshot::SnapshotNode
+};
 
 #[derive(Clone, Debug)]
-pub struct Node<Key, const ORDER: usize>
+pub struct Node<Key, const ORDER: usize, Value = ()>
 where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]: {
     pub(crate) size: usize,
     pub(crate) is_leaf: bool,
-    pub(crate) parent: Option<Weak<RefCell<Node<Key, ORDER>>>>,
+    pub(crate) count_unique: usize,
+    pub(crate) count_agg: usize,
+    pub(crate) weighted_count_agg: f32,
+    pub(crate) parent: Option<Weak<RefCell<Node<Key, ORDER, Value>>>>,
     pub(crate) keys: [Option<Key>; ORDER],
-    pub(crate) elements: [Option<Rc<RefCell<Element<Key, ORDER>>>>; ORDER],
-    pub(crate) children: [Option<Rc<RefCell<Node<Key, ORDER>>>>; ORDER + 1]
+    #[allow(clippy::type_complexity)]
+    pub(crate) elements: [Option<Rc<RefCell<Element<Key, ORDER, Value>>>>; ORDER],
+    #[allow(clippy::type_complexity)]
+    pub(crate) children: [Option<Rc<RefCell<Node<Key, ORDER, Value>>>>; ORDER + 1],
+    pub(crate) frozen: Option<Weak<SnapshotNode<Key, Value>>>
 }
 
-impl<Key, const ORDER: usize> Node<Key, ORDER> 
+impl<Key, const ORDER: usize, Value> Node<Key, ORDER, Value> 
 where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]: {
     pub fn new(
-        is_leaf: bool, parent: Option<Weak<RefCell<Node<Key, ORDER>>>>
-    ) -> Node<Key, ORDER> {
+        is_leaf: bool, parent: Option<Weak<RefCell<Node<Key, ORDER, Value>>>>
+    ) -> Node<Key, ORDER, Value> {
         Node {
             size: 0,
             is_leaf,
+            count_unique: 0,
+            count_agg: 0,
+            weighted_count_agg: 0.0f32,
             parent,
             keys: [(); ORDER].map(|_| None),
             elements: [(); ORDER].map(|_| None),
-            children: [(); ORDER + 1].map(|_| None)
+            children: [(); ORDER + 1].map(|_| None),
+            frozen: None
         }
     }
 
     pub fn find_element(&self, key: &Key, algorithm: SearchAlgorithm)
-    -> Option<Ref<Element<Key, ORDER>>> {
+    -> Option<Ref<'_, Element<Key, ORDER, Value>>> {
         match algorithm {
             SearchAlgorithm::LeftSearch => {
                 for i in 0..self.size {
@@ -46,7 +60,7 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
                         return Some(self.elements[i].as_ref().unwrap().as_ref().borrow()) 
                     }
                 }
-                return None
+                None
             },
             SearchAlgorithm::RightSearch => {
                 for i in (0..self.size).rev() {
@@ -54,7 +68,7 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
                         return Some(self.elements[i].as_ref().unwrap().as_ref().borrow()) 
                     }
                 }
-                return None
+                None
             },
             SearchAlgorithm::BinarySearch => {
                 let mut left_index = 0;
@@ -72,13 +86,13 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
                         );
                     }
                 }
-                return None
+                None
             }
         }
     }
 
     pub fn find_element_mut(&self, key: &Key, algorithm: SearchAlgorithm)
-    -> Option<RefMut<Element<Key, ORDER>>> {
+    -> Option<RefMut<'_, Element<Key, ORDER, Value>>> {
         match algorithm {
             SearchAlgorithm::LeftSearch => {
                 for i in 0..self.size {
@@ -86,7 +100,7 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
                         return Some(self.elements[i].as_ref().unwrap().as_ref().borrow_mut()) 
                     }
                 }
-                return None
+                None
             },
             SearchAlgorithm::RightSearch => {
                 for i in (0..self.size).rev() {
@@ -94,7 +108,7 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
                         return Some(self.elements[i].as_ref().unwrap().as_ref().borrow_mut()) 
                     }
                 }
-                return None
+                None
             },
             SearchAlgorithm::BinarySearch => {
                 let mut left_index = 0;
@@ -112,27 +126,28 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
                         );
                     }
                 }
-                return None
+                None
             }
         }
     }
 
-    pub fn split_child(node_ptr: &Rc<RefCell<Node<Key, ORDER>>>, child_index: usize) {
+    pub fn split_child(node_ptr: &Rc<RefCell<Node<Key, ORDER, Value>>>, child_index: usize) {
         let node_size = node_ptr.borrow_mut().size;
 
         let left_node = 
             node_ptr.borrow().children[child_index].as_ref().unwrap().clone();
         left_node.borrow_mut().parent = Some(Rc::downgrade(node_ptr));
 
-        let mut right_node = Node::<Key, ORDER>::new(
+        let mut right_node = Node::<Key, ORDER, Value>::new(
             left_node.borrow().is_leaf, Some(Rc::downgrade(node_ptr))
         );
         right_node.parent = Some(Rc::downgrade(node_ptr));
         
-        right_node.size = Self::MIN_ELEMENTS;
-        left_node.borrow_mut().size = Self::MIN_ELEMENTS;
+        let right_size = ORDER - Self::T_OFFSET;
+        right_node.size = right_size;
+        left_node.borrow_mut().size = Self::MID_INDEX;
 
-        for i in 0..Self::MIN_ELEMENTS {
+        for i in 0..right_size {
             right_node.elements[i] = left_node.borrow_mut().elements[Self::T_OFFSET + i].take();
             right_node.keys[i] = left_node.borrow_mut().keys[Self::T_OFFSET + i].take();
         }
@@ -150,7 +165,7 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
             node_ptr.borrow().children[child_index + 1].as_ref().unwrap().clone();
         
         if !left_node.borrow().is_leaf {
-            for i in 0..Self::MIN_CHILDREN {
+            for i in 0..=right_size {
                 right_node.borrow_mut().children[i] = 
                     left_node.borrow_mut().children[Self::T_OFFSET + i].take();
                 right_node.borrow_mut().children[i].as_ref().unwrap().borrow_mut().parent 
@@ -163,10 +178,15 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
         node_ptr.borrow_mut().keys[child_index] = 
             left_node.borrow_mut().keys[Self::MID_INDEX].take();
         node_ptr.borrow_mut().size += 1;
+
+        left_node.borrow_mut().update_counts();
+        right_node.borrow_mut().update_counts();
+        node_ptr.borrow_mut().update_counts();
     }
 
+    #[allow(clippy::type_complexity)]
     pub(crate) fn insert_existing_key(&self, key: &Key, left_search: bool)
-    -> (Option<Rc<RefCell<Element<Key, ORDER>>>>, usize) {
+    -> (Option<Rc<RefCell<Element<Key, ORDER, Value>>>>, usize) {
         let mut index;
         if left_search {
             index = 0usize;
@@ -181,6 +201,7 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
             if index < self.size && key == current_key {
                 let element = self.elements[index].as_ref().unwrap().clone();
                 element.borrow_mut().counter += 1;
+                element.borrow_mut().weighted_counter += 1.0f32;
                 return (Some(element), index)
             }
         } else {
@@ -195,12 +216,14 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
             } else if key == current_key {
                 let element = self.elements[index].as_ref().unwrap().clone();
                 element.borrow_mut().counter += 1;
+                element.borrow_mut().weighted_counter += 1.0f32;
                 return (Some(element), index)
             }
 
             if index < self.size && key == current_key {
                 let element = self.elements[index].as_ref().unwrap().clone();
                 element.borrow_mut().counter += 1;
+                element.borrow_mut().weighted_counter += 1.0f32;
                 return (Some(element), index)
             }
         }
@@ -208,8 +231,15 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
     }
 
     pub(crate) fn insert_key_leaf(
-        node: &Rc<RefCell<Node<Key, ORDER>>>, key: &Key
-    ) -> Rc<RefCell<Element<Key, ORDER>>> {
+        node: &Rc<RefCell<Node<Key, ORDER, Value>>>, key: &Key
+    ) -> Rc<RefCell<Element<Key, ORDER, Value>>>
+    where Value: Default {
+        Self::insert_key_leaf_with(node, key, Value::default())
+    }
+
+    pub(crate) fn insert_key_leaf_with(
+        node: &Rc<RefCell<Node<Key, ORDER, Value>>>, key: &Key, value: Value
+    ) -> Rc<RefCell<Element<Key, ORDER, Value>>> {
         let node_size = node.borrow().size;
 
         let mut index = node_size - 1;
@@ -230,7 +260,7 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
             index += 1;
         }
         
-        let new_element = Rc::new(RefCell::new(Element::new(key)));
+        let new_element = Rc::new(RefCell::new(Element::with_value(key, value)));
         node.borrow_mut().elements[index] = Some(new_element.clone());
         node.borrow_mut().keys[index] = Some(key.clone());
 
@@ -240,41 +270,44 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
             if index == 0 {
                 if let Some(next) = node.borrow().elements[1].as_ref() {
                     next_ptr = Some(next.clone());
-                    prev_ptr = match next.as_ref().borrow().prev.as_ref() {
-                        Some(v) => Some(v.upgrade().unwrap()),
-                        None => None
-                    };
+                    prev_ptr = next.as_ref().borrow().prev.as_ref().map(|v| v.upgrade().unwrap());
                 }
             } else {
                 if let Some(prev) = node.borrow().elements[index - 1].as_ref() {
                     prev_ptr = Some(prev.clone());
-                    next_ptr = match prev.as_ref().borrow().next.as_ref() {
-                        Some(e) => Some(e.upgrade().unwrap().clone()),
-                        None => None
-                    };
+                    next_ptr = prev.as_ref().borrow().next.as_ref().map(|e| e.upgrade().unwrap().clone());
                 }
             }
         }
 
-        Element::<Key, ORDER>::set_connections(
+        Element::<Key, ORDER, Value>::set_connections(
             &new_element, prev_ptr.as_ref(), next_ptr.as_ref()
         );
         node.borrow_mut().size += 1;
+        Self::update_counts_upwards(node);
 
         new_element
     }
 
-    pub const MIN_CHILDREN: usize = (ORDER + 1) / 2;
+    fn key_ordering(key: &Key, other: &Key) -> Option<Ordering> { key.partial_cmp(other) }
+
+    fn clone_key(key: &Key) -> Key { key.clone() }
+
+    fn clear_frozen(&mut self) { self.frozen = None; }
+
+    node_algorithms!(Rc, RefCell, Element<Key, ORDER, Value>);
+
+    pub const MIN_CHILDREN: usize = ORDER.div_ceil(2);
     pub const MAX_CHILDREN: usize = ORDER + 1;
-    pub const MIN_ELEMENTS: usize = (ORDER + 1) / 2 - 1;
+    pub const MIN_ELEMENTS: usize = ORDER.div_ceil(2) - 1;
     pub const MAX_ELEMENTS: usize = ORDER;
-    pub const MIN_KEYS: usize = (ORDER + 1) / 2 - 1;
+    pub const MIN_KEYS: usize = ORDER.div_ceil(2) - 1;
     pub const MAX_KEYS: usize = ORDER;
-    pub(crate) const MID_INDEX: usize = (ORDER + 1) / 2 - 1;
-    pub(crate) const T_OFFSET: usize = (ORDER + 1) / 2;
+    pub(crate) const MID_INDEX: usize = ORDER.div_ceil(2) - 1;
+    pub(crate) const T_OFFSET: usize = ORDER.div_ceil(2);
 }
 
-impl<Key, const ORDER: usize> Display for Node<Key, ORDER> 
+impl<Key, const ORDER: usize, Value> Display for Node<Key, ORDER, Value> 
 where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]: {
     fn fmt(&self, f: &mut Formatter) -> Result {
         let mut node: String = format!(
@@ -309,6 +342,7 @@ where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_borrow, clippy::bool_assert_comparison)]
 mod tests {
     use std::{
         rc::Rc,
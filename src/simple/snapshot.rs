@@ -0,0 +1,382 @@
+use std::{
+    fmt::{ Display, Formatter, Result as FmtResult },
+    rc::{ Rc, Weak },
+    cell::RefCell,
+    ops::{ RangeBounds, Bound::{ self, * } }
+};
+
+use bionet_common::distances::Distance;
+
+use super::{
+    element::Element,
+    node::Node,
+    graph::ASAGraph
+};
+
+#[derive(Clone, Debug)]
+pub struct SnapshotElement<Key, Value = ()>
+where Key: Clone + Display + PartialOrd + PartialEq + Distance {
+    pub key: Key,
+    pub counter: usize,
+    pub weighted_counter: f32,
+    pub value: Value
+}
+
+impl<Key, const ORDER: usize, Value> From<&Element<Key, ORDER, Value>> for SnapshotElement<Key, Value>
+where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:, Value: Clone {
+    fn from(element: &Element<Key, ORDER, Value>) -> SnapshotElement<Key, Value> {
+        SnapshotElement {
+            key: element.key.clone(),
+            counter: element.counter,
+            weighted_counter: element.weighted_counter,
+            value: element.value.clone()
+        }
+    }
+}
+
+impl<Key, Value> Display for SnapshotElement<Key, Value>
+where Key: Clone + Display + PartialOrd + PartialEq + Distance {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "[{}:{}]", self.key, self.counter)
+    }
+}
+
+#[derive(Debug)]
+pub struct SnapshotNode<Key, Value = ()>
+where Key: Clone + Display + PartialOrd + PartialEq + Distance {
+    pub(crate) elements: Vec<SnapshotElement<Key, Value>>,
+    pub(crate) children: Vec<Rc<SnapshotNode<Key, Value>>>,
+    pub(crate) count_unique: usize,
+    pub(crate) count_agg: usize,
+    pub(crate) weighted_count_agg: f32
+}
+
+impl<Key, Value> SnapshotNode<Key, Value>
+where Key: Clone + Display + PartialOrd + PartialEq + Distance {
+    fn is_leaf(&self) -> bool { self.children.is_empty() }
+}
+
+#[derive(Clone, Debug)]
+pub struct Snapshot<Key, Value = ()>
+where Key: Clone + Display + PartialOrd + PartialEq + Distance {
+    pub name: Rc<str>,
+    pub(crate) root: Rc<SnapshotNode<Key, Value>>
+}
+
+impl<Key, Value> Snapshot<Key, Value>
+where Key: Clone + Display + PartialOrd + PartialEq + Distance {
+    pub fn search(&self, key: &Key) -> Option<&SnapshotElement<Key, Value>> {
+        let mut node = &*self.root;
+        loop {
+            let index = node.elements.iter()
+                .position(|element| *key <= element.key)
+                .unwrap_or(node.elements.len());
+            if index < node.elements.len() && *key == node.elements[index].key {
+                return Some(&node.elements[index])
+            }
+            if node.is_leaf() { return None }
+            node = &node.children[index];
+        }
+    }
+
+    pub fn iter(&self) -> SnapshotIterator<'_, Key, Value> {
+        self.range(..)
+    }
+
+    pub fn range<R: RangeBounds<Key>>(&self, range: R) -> SnapshotIterator<'_, Key, Value> {
+        let mut stack = Vec::new();
+        let mut node = &*self.root;
+        loop {
+            let index = node.elements.iter()
+                .position(|element| match range.start_bound() {
+                    Included(key) => element.key >= *key,
+                    Excluded(key) => element.key > *key,
+                    Unbounded => true
+                })
+                .unwrap_or(node.elements.len());
+            stack.push((node, index));
+            if node.is_leaf() { break }
+            node = &node.children[index];
+        }
+
+        SnapshotIterator { stack, end: range.end_bound().cloned() }
+    }
+
+    pub fn key_min(&self) -> Option<&Key> {
+        let mut node = &*self.root;
+        while !node.is_leaf() { node = node.children.first().unwrap(); }
+        node.elements.first().map(|element| &element.key)
+    }
+
+    pub fn key_max(&self) -> Option<&Key> {
+        let mut node = &*self.root;
+        while !node.is_leaf() { node = node.children.last().unwrap(); }
+        node.elements.last().map(|element| &element.key)
+    }
+
+    pub fn count_elements_unique(&self) -> usize { self.root.count_unique }
+
+    pub fn count_elements_agg(&self) -> usize { self.root.count_agg }
+
+    pub fn count_elements_weighted(&self) -> f32 { self.root.weighted_count_agg }
+
+    pub fn rank(&self, key: &Key) -> usize { self.count_below(key, false) }
+
+    pub fn select(&self, index: usize) -> Option<&SnapshotElement<Key, Value>> {
+        let mut index = index;
+        let mut node = &*self.root;
+        loop {
+            let mut child_index = node.elements.len();
+            for i in 0..node.elements.len() {
+                if !node.is_leaf() {
+                    let child_count = node.children[i].count_unique;
+                    if index < child_count {
+                        child_index = i;
+                        break
+                    }
+                    index -= child_count;
+                }
+                if index == 0 { return Some(&node.elements[i]) }
+                index -= 1;
+            }
+            if node.is_leaf() { return None }
+            node = &node.children[child_index];
+        }
+    }
+
+    pub fn count_in_range<R: RangeBounds<Key>>(&self, range: R) -> usize {
+        let lower = match range.start_bound() {
+            Included(key) => self.count_below(key, false),
+            Excluded(key) => self.count_below(key, true),
+            Unbounded => 0
+        };
+        let upper = match range.end_bound() {
+            Included(key) => self.count_below(key, true),
+            Excluded(key) => self.count_below(key, false),
+            Unbounded => self.count_elements_unique()
+        };
+        upper.saturating_sub(lower)
+    }
+
+    fn count_below(&self, key: &Key, include_key: bool) -> usize {
+        let mut count_unique = 0;
+        let mut node = &*self.root;
+        loop {
+            let mut index = 0;
+            while index < node.elements.len() {
+                let element_key = &node.elements[index].key;
+                if !(*element_key < *key || (include_key && *element_key == *key)) { break }
+                if !node.is_leaf() { count_unique += node.children[index].count_unique; }
+                count_unique += 1;
+                index += 1;
+            }
+            if node.is_leaf() { return count_unique }
+            node = &node.children[index];
+        }
+    }
+}
+
+pub struct SnapshotIterator<'a, Key, Value = ()>
+where Key: Clone + Display + PartialOrd + PartialEq + Distance {
+    stack: Vec<(&'a SnapshotNode<Key, Value>, usize)>,
+    end: Bound<Key>
+}
+
+impl<'a, Key, Value> Iterator for SnapshotIterator<'a, Key, Value>
+where Key: Clone + Display + PartialOrd + PartialEq + Distance {
+    type Item = &'a SnapshotElement<Key, Value>;
+    fn next(&mut self) -> Option<&'a SnapshotElement<Key, Value>> {
+        loop {
+            let (node, index) = *self.stack.last()?;
+            if index == node.elements.len() {
+                self.stack.pop();
+                continue
+            }
+
+            let element = &node.elements[index];
+            let is_past_end = match &self.end {
+                Included(key) => element.key > *key,
+                Excluded(key) => element.key >= *key,
+                Unbounded => false
+            };
+            if is_past_end {
+                self.stack.clear();
+                return None
+            }
+
+            self.stack.last_mut().unwrap().1 += 1;
+            if !node.is_leaf() {
+                let mut child = &*node.children[index + 1];
+                loop {
+                    self.stack.push((child, 0));
+                    if child.is_leaf() { break }
+                    child = &child.children[0];
+                }
+            }
+            return Some(element)
+        }
+    }
+}
+
+impl<Key, const ORDER: usize, Value> Node<Key, ORDER, Value>
+where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:, Value: Clone {
+    pub(crate) fn freeze(node: &Rc<RefCell<Node<Key, ORDER, Value>>>) -> Rc<SnapshotNode<Key, Value>> {
+        if let Some(frozen) = node.borrow().frozen.as_ref().and_then(Weak::upgrade) { return frozen }
+
+        let frozen = {
+            let node_ref = node.borrow();
+            let elements = node_ref.elements[..node_ref.size].iter()
+                .flatten()
+                .map(|element| SnapshotElement::from(&*element.borrow()))
+                .collect();
+            let children = if node_ref.is_leaf {
+                Vec::new()
+            } else {
+                node_ref.children[..=node_ref.size].iter().flatten().map(Self::freeze).collect()
+            };
+            Rc::new(SnapshotNode {
+                elements,
+                children,
+                count_unique: node_ref.count_unique,
+                count_agg: node_ref.count_agg,
+                weighted_count_agg: node_ref.weighted_count_agg
+            })
+        };
+        node.borrow_mut().frozen = Some(Rc::downgrade(&frozen));
+        frozen
+    }
+}
+
+impl<Key, const ORDER: usize, Value> ASAGraph<Key, ORDER, Value>
+where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]:, Value: Clone {
+    pub fn snapshot(&self) -> Snapshot<Key, Value> {
+        Snapshot { name: Rc::from(self.name.as_str()), root: Node::freeze(&self.root) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use rand::{ Rng, SeedableRng, rngs::StdRng };
+
+    use super::super::{ graph::ASAGraph, node::Node, entry::Entry };
+
+    #[test]
+    fn snapshot_is_frozen() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 0..50 { graph.insert(&(i * 2)); }
+        let snapshot = graph.snapshot();
+
+        for i in 0..50 { graph.insert(&(i * 2 + 1)); }
+        graph.insert(&10);
+        graph.remove_range(20..=22);
+
+        assert_eq!(snapshot.count_elements_unique(), 50);
+        assert_eq!(snapshot.count_elements_agg(), 50);
+        assert_eq!(snapshot.search(&10).unwrap().counter, 1);
+        assert_eq!(snapshot.search(&20).unwrap().counter, 1);
+        assert!(snapshot.search(&11).is_none());
+        assert_eq!(snapshot.key_min(), Some(&0));
+        assert_eq!(snapshot.key_max(), Some(&98));
+        let keys = snapshot.iter().map(|element| element.key).collect::<Vec<_>>();
+        assert_eq!(keys, (0..50).map(|i| i * 2).collect::<Vec<_>>());
+        let keys = snapshot.range(9..=15).map(|element| element.key).collect::<Vec<_>>();
+        assert_eq!(keys, vec![10, 12, 14]);
+        assert_eq!(snapshot.rank(&30), 15);
+        assert_eq!(snapshot.select(15).unwrap().key, 30);
+        assert_eq!(snapshot.count_in_range(10..20), 5);
+
+        let snapshot_clone = snapshot.clone();
+        assert!(Rc::ptr_eq(&snapshot.root, &snapshot_clone.root));
+        assert_eq!(graph.count_elements_unique(), 97);
+        assert_eq!(graph.search(&10).unwrap().borrow().counter, 2);
+    }
+
+    #[test]
+    fn structural_sharing() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        for i in 0..100 { graph.insert(&i); }
+        let first = graph.snapshot();
+        let second = graph.snapshot();
+        assert!(Rc::ptr_eq(&first.root, &second.root));
+
+        graph.insert(&0);
+        let third = graph.snapshot();
+        assert!(!Rc::ptr_eq(&first.root, &third.root));
+        let shared = first.root.children.iter()
+            .filter(|child| third.root.children.iter().any(|other| Rc::ptr_eq(child, other)))
+            .count();
+        assert_eq!(shared, first.root.children.len() - 1);
+        assert_eq!(first.search(&0).unwrap().counter, 1);
+        assert_eq!(third.search(&0).unwrap().counter, 2);
+
+        let child = graph.root.borrow().children[0].clone().unwrap();
+        assert!(child.borrow().frozen.as_ref().unwrap().upgrade().is_some());
+        drop((first, second, third));
+        assert!(graph.root.borrow().frozen.as_ref().unwrap().upgrade().is_none());
+        assert!(child.borrow().frozen.as_ref().unwrap().upgrade().is_none());
+        let fourth = graph.snapshot();
+        assert_eq!(fourth.search(&0).unwrap().counter, 2);
+        assert!(Rc::ptr_eq(&Node::freeze(&child), &fourth.root.children[0]));
+    }
+
+    #[test]
+    fn counter_changes_between_snapshots() {
+        let mut graph = ASAGraph::<i32, 3, Vec<usize>>::new("test");
+        for i in 0..50 { graph.insert(&i); }
+        let first = graph.snapshot();
+
+        match graph.entry(&10) {
+            Entry::Occupied(mut entry) => assert_eq!(entry.set_counter(5), Some(1)),
+            Entry::Vacant(_) => panic!("10 should be occupied")
+        }
+        assert_eq!(graph.cursor_mut().increment_counter(), Some(2));
+        graph.insert_weighted(&40, 0.5f32);
+        assert!(graph.update_value(&20, |ids| ids.push(1)));
+        let second = graph.snapshot();
+
+        assert_eq!(first.search(&10).unwrap().counter, 1);
+        assert_eq!(first.search(&0).unwrap().counter, 1);
+        assert_eq!(first.search(&40).unwrap().weighted_counter, 1.0f32);
+        assert!(first.search(&20).unwrap().value.is_empty());
+        assert_eq!(first.count_elements_agg(), 50);
+        assert_eq!(second.search(&10).unwrap().counter, 5);
+        assert_eq!(second.search(&0).unwrap().counter, 2);
+        assert_eq!(second.search(&40).unwrap().weighted_counter, 1.5f32);
+        assert_eq!(second.search(&20).unwrap().value, vec![1]);
+        assert_eq!(second.count_elements_agg(), 56);
+        assert_eq!(second.count_elements_weighted(), 55.5f32);
+    }
+
+    #[test]
+    fn snapshots_follow_history() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut graph = ASAGraph::<i32, 4>::new("test");
+        let mut history = Vec::new();
+
+        for step in 0..2_000 {
+            let key = rng.gen_range(0..200);
+            if rng.gen_range(0..4) == 0 {
+                graph.remove_range(key..(key + 3));
+            } else {
+                graph.insert(&key);
+            }
+            if step % 50 == 0 {
+                let model = graph.iter()
+                    .map(|element| (element.borrow().key, element.borrow().counter))
+                    .collect::<Vec<_>>();
+                history.push((graph.snapshot(), model));
+            }
+        }
+
+        for (snapshot, model) in history {
+            let elements = snapshot.iter()
+                .map(|element| (element.key, element.counter))
+                .collect::<Vec<_>>();
+            assert_eq!(elements, model);
+            assert_eq!(snapshot.count_elements_unique(), model.len());
+            assert_eq!(snapshot.count_elements_agg(), model.iter().map(|(_, c)| c).sum::<usize>());
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,251 @@
+use std::{
+    fmt::{ Display, Formatter, Result as FmtResult },
+    rc::Rc,
+    cell::RefCell,
+    cmp::Ordering::*
+};
+
+use bionet_common::distances::Distance;
+
+use super::{
+    element::Element,
+    node::Node,
+    graph::ASAGraph
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationError<Key> {
+    NodeSize { depth: usize, size: usize },
+    MissingChild { depth: usize, index: usize },
+    ParentPointer { depth: usize, index: usize },
+    LeafDepth { expected: usize, found: usize },
+    AggregateCounts { depth: usize },
+    MissingElement { key: Key },
+    KeyMismatch { key: Key, element_key: Key },
+    UnsortedKeys { key: Key, next_key: Key },
+    BrokenChain { key: Key },
+    IncompleteChain { tree_elements: usize, chain_elements: usize },
+    ElementMin,
+    ElementMax,
+    KeyMin,
+    KeyMax
+}
+
+impl<Key: Display> Display for ValidationError<Key> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            ValidationError::NodeSize { depth, size } => {
+                write!(f, "node at depth {} has {} keys", depth, size)
+            },
+            ValidationError::MissingChild { depth, index } => {
+                write!(f, "node at depth {} is missing child {}", depth, index)
+            },
+            ValidationError::ParentPointer { depth, index } => {
+                write!(f, "child {} of node at depth {} has a wrong parent pointer", index, depth)
+            },
+            ValidationError::LeafDepth { expected, found } => {
+                write!(f, "leaf at depth {} while other leaves are at depth {}", found, expected)
+            },
+            ValidationError::AggregateCounts { depth } => {
+                write!(f, "node at depth {} has stale aggregate counts", depth)
+            },
+            ValidationError::MissingElement { key } => write!(f, "key {} has no element", key),
+            ValidationError::KeyMismatch { key, element_key } => {
+                write!(f, "key {} points to element with key {}", key, element_key)
+            },
+            ValidationError::UnsortedKeys { key, next_key } => {
+                write!(f, "key {} is followed by key {}", key, next_key)
+            },
+            ValidationError::BrokenChain { key } => {
+                write!(f, "element chain is broken at key {}", key)
+            },
+            ValidationError::IncompleteChain { tree_elements, chain_elements } => {
+                write!(f, "element chain has {} of {} elements", chain_elements, tree_elements)
+            },
+            ValidationError::ElementMin => write!(f, "element_min is not the first element"),
+            ValidationError::ElementMax => write!(f, "element_max is not the last element"),
+            ValidationError::KeyMin => write!(f, "key_min is not the smallest key"),
+            ValidationError::KeyMax => write!(f, "key_max is not the largest key")
+        }
+    }
+}
+
+fn is_same<T>(element: Option<&Rc<T>>, expected: Option<&Rc<T>>) -> bool {
+    match (element, expected) {
+        (Some(element), Some(expected)) => Rc::ptr_eq(element, expected),
+        (None, None) => true,
+        _ => false
+    }
+}
+
+impl<Key, const ORDER: usize, Value> ASAGraph<Key, ORDER, Value>
+where Key: Clone + Display + PartialOrd + PartialEq + Distance, [(); ORDER + 1]: {
+    pub fn validate(&self) -> Result<(), Vec<ValidationError<Key>>> {
+        let mut errors = Vec::new();
+        let mut elements = Vec::new();
+        let mut leaf_depth = None;
+
+        if self.root.borrow().parent.is_some() {
+            errors.push(ValidationError::ParentPointer { depth: 0, index: 0 });
+        }
+        Self::validate_node(&self.root, 0, &mut leaf_depth, &mut elements, &mut errors);
+
+        for pair in elements.windows(2) {
+            let (key, next_key) = (&pair[0].borrow().key, &pair[1].borrow().key);
+            if key.partial_cmp(next_key) != Some(Less) {
+                errors.push(ValidationError::UnsortedKeys { key: key.clone(), next_key: next_key.clone() });
+            }
+        }
+
+        self.validate_chain(&elements, &mut errors);
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    fn validate_node(
+        node: &Rc<RefCell<Node<Key, ORDER, Value>>>,
+        depth: usize,
+        leaf_depth: &mut Option<usize>,
+        elements: &mut Vec<Rc<RefCell<Element<Key, ORDER, Value>>>>,
+        errors: &mut Vec<ValidationError<Key>>
+    ) {
+        let node_ref = node.borrow();
+        let size = node_ref.size;
+        let is_size_valid = if depth == 0 {
+            size <= Node::<Key, ORDER, Value>::MAX_KEYS && (size > 0 || node_ref.is_leaf)
+        } else {
+            size >= Node::<Key, ORDER, Value>::MIN_KEYS && size <= Node::<Key, ORDER, Value>::MAX_KEYS
+        };
+        if !is_size_valid {
+            errors.push(ValidationError::NodeSize { depth, size });
+            if size > ORDER { return }
+        }
+
+        let first_element = elements.len();
+        let mut count_agg = 0;
+        for index in 0..=size {
+            if !node_ref.is_leaf {
+                match &node_ref.children[index] {
+                    Some(child) => {
+                        let parent = child.borrow().parent.as_ref().and_then(|parent| parent.upgrade());
+                        if !parent.is_some_and(|parent| Rc::ptr_eq(&parent, node)) {
+                            errors.push(ValidationError::ParentPointer { depth, index });
+                        }
+                        Self::validate_node(child, depth + 1, leaf_depth, elements, errors);
+                        count_agg += child.borrow().count_agg;
+                    },
+                    None => errors.push(ValidationError::MissingChild { depth, index })
+                }
+            }
+            if index == size { break }
+
+            let key = match &node_ref.keys[index] {
+                Some(key) => key,
+                None => continue
+            };
+            match &node_ref.elements[index] {
+                Some(element) => {
+                    let element_key = &element.borrow().key;
+                    if element_key != key {
+                        errors.push(ValidationError::KeyMismatch {
+                            key: key.clone(), element_key: element_key.clone()
+                        });
+                    }
+                    count_agg += element.borrow().counter;
+                    elements.push(element.clone());
+                },
+                None => errors.push(ValidationError::MissingElement { key: key.clone() })
+            }
+        }
+
+        if node_ref.is_leaf {
+            match *leaf_depth {
+                Some(expected) if expected != depth => {
+                    errors.push(ValidationError::LeafDepth { expected, found: depth });
+                },
+                Some(_) => {},
+                None => *leaf_depth = Some(depth)
+            }
+        }
+
+        if node_ref.count_unique != elements.len() - first_element || node_ref.count_agg != count_agg {
+            errors.push(ValidationError::AggregateCounts { depth });
+        }
+    }
+
+    fn validate_chain(
+        &self,
+        elements: &[Rc<RefCell<Element<Key, ORDER, Value>>>],
+        errors: &mut Vec<ValidationError<Key>>
+    ) {
+        if !is_same(self.element_min.as_ref(), elements.first()) { errors.push(ValidationError::ElementMin) }
+        if !is_same(self.element_max.as_ref(), elements.last()) { errors.push(ValidationError::ElementMax) }
+        if self.key_min != elements.first().map(|element| element.borrow().key.clone()) {
+            errors.push(ValidationError::KeyMin);
+        }
+        if self.key_max != elements.last().map(|element| element.borrow().key.clone()) {
+            errors.push(ValidationError::KeyMax);
+        }
+
+        let mut chain_elements = 0;
+        let mut prev: Option<Rc<RefCell<Element<Key, ORDER, Value>>>> = None;
+        let mut current = self.element_min.clone();
+        while let Some(element) = current {
+            if chain_elements == elements.len() || !Rc::ptr_eq(&element, &elements[chain_elements]) {
+                errors.push(ValidationError::BrokenChain { key: element.borrow().key.clone() });
+                break
+            }
+            let element_prev = element.borrow().prev.as_ref().and_then(|prev| prev.upgrade());
+            if !is_same(element_prev.as_ref(), prev.as_ref()) {
+                errors.push(ValidationError::BrokenChain { key: element.borrow().key.clone() });
+            }
+            chain_elements += 1;
+            current = element.borrow().next.as_ref().and_then(|next| next.upgrade());
+            prev = Some(element);
+        }
+
+        if chain_elements != elements.len() {
+            errors.push(ValidationError::IncompleteChain { tree_elements: elements.len(), chain_elements });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::{ super::graph::ASAGraph, ValidationError };
+
+    #[test]
+    fn validate() {
+        let mut graph = ASAGraph::<i32, 3>::new("test");
+        assert_eq!(graph.validate(), Ok(()));
+        for i in 0..100 { graph.insert(&(i * 7 % 50)); }
+        assert_eq!(graph.validate(), Ok(()));
+        graph.remove_range(10..30);
+        assert_eq!(graph.validate(), Ok(()));
+
+        graph.key_max = Some(1000);
+        graph.root.borrow_mut().count_agg += 1;
+        let errors = graph.validate().unwrap_err();
+        assert_eq!(errors, vec![ValidationError::AggregateCounts { depth: 0 }, ValidationError::KeyMax]);
+        assert_eq!(errors[1].to_string(), "key_max is not the largest key");
+        graph.key_max = Some(49);
+        graph.root.borrow_mut().count_agg -= 1;
+
+        let element = graph.search(&35).unwrap();
+        element.borrow_mut().next = None;
+        assert_eq!(
+            graph.validate().unwrap_err(),
+            vec![ValidationError::IncompleteChain { tree_elements: 30, chain_elements: 16 }]
+        );
+        let next = graph.search(&36).unwrap();
+        element.borrow_mut().next = Some(Rc::downgrade(&next));
+        assert_eq!(graph.validate(), Ok(()));
+
+        graph.search(&40).unwrap().borrow_mut().key = 41;
+        let errors = graph.validate().unwrap_err();
+        assert!(errors.contains(&ValidationError::KeyMismatch { key: 40, element_key: 41 }));
+        assert!(errors.contains(&ValidationError::UnsortedKeys { key: 41, next_key: 41 }));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,522 @@
+macro_rules! differential_tests {
+    ($graph:ident) => {
+        differential_tests!($graph, |_: &mut $graph<i32, ORDER>, _: &mut BTreeMap<i32, usize>, _: i32, _: bool| {});
+    };
+    ($graph:ident, $remove:expr) => {
+        fn assert_matches_model<const ORDER: usize>(graph: &$graph<i32, ORDER>, model: &BTreeMap<i32, usize>, context: &str)
+        where [(); ORDER + 1]: {
+            assert_eq!(graph.validate(), Ok(()), "{}", context);
+            let elements = graph.iter().map(|e| (e.borrow().key, e.borrow().counter)).collect::<Vec<_>>();
+            assert_eq!(elements, model.iter().map(|(k, c)| (*k, *c)).collect::<Vec<_>>(), "{}", context);
+            assert_eq!(graph.count_elements_unique(), model.len(), "{}", context);
+            assert_eq!(graph.count_elements_agg(), model.values().sum::<usize>(), "{}", context);
+            assert_eq!(graph.key_min, model.keys().next().cloned(), "{}", context);
+            assert_eq!(graph.key_max, model.keys().next_back().cloned(), "{}", context);
+        }
+
+        fn differential<const ORDER: usize>(seed: u64, steps: usize, key_space: i32)
+        where [(); ORDER + 1]: {
+            use rand::{ Rng, SeedableRng, rngs::StdRng };
+
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut graph = $graph::<i32, ORDER>::new("test");
+            let mut model = BTreeMap::<i32, usize>::new();
+
+            for step in 0..steps {
+                let key = rng.gen_range(-key_space..key_space);
+                let context = format!("seed {} step {} key {}", seed, step, key);
+                match rng.gen_range(0..20) {
+                    0..=7 => {
+                        let counter = model.entry(key).or_insert(0);
+                        *counter += 1;
+                        assert_eq!(graph.insert(&key).borrow().counter, *counter, "insert {}", context);
+                    },
+                    8 => {
+                        let n = rng.gen_range(0..4);
+                        let expected = match model.get_mut(&key) {
+                            Some(counter) => { *counter += n; Some(*counter) },
+                            None if n > 0 => { model.insert(key, n); Some(n) },
+                            None => None
+                        };
+                        let counter = graph.insert_n(&key, n).map(|e| e.borrow().counter);
+                        assert_eq!(counter, expected, "insert_n {} {}", n, context);
+                    },
+                    9..=10 => {
+                        let element = graph.search(&key);
+                        assert_eq!(element.map(|e| e.borrow().counter), model.get(&key).cloned(), "search {}", context);
+                    },
+                    11 => {
+                        let end = rng.gen_range(key..=key_space);
+                        let expected = model.range(key..end).count();
+                        assert_eq!(graph.count_in_range(key..end), expected, "count_in_range {}..{}", key, end);
+                        assert_eq!(graph.rank(&key), model.range(..key).count(), "rank {}", key);
+                        let keys = graph.range(key..end).map(|e| e.borrow().key).collect::<Vec<_>>();
+                        assert_eq!(keys, model.range(key..end).map(|(k, _)| *k).collect::<Vec<_>>());
+                        let neighbours = [
+                            graph.floor(&key), graph.ceiling(&key), graph.predecessor(&key), graph.successor(&key)
+                        ].map(|element| element.map(|e| e.borrow().key));
+                        assert_eq!(neighbours, [
+                            model.range(..=key).next_back(), model.range(key..).next(),
+                            model.range(..key).next_back(), model.range(key + 1..).next()
+                        ].map(|entry| entry.map(|(k, _)| *k)), "neighbours {}", key);
+                    },
+                    12..=13 => ($remove)(&mut graph, &mut model, key, rng.gen_bool(0.5)),
+                    14 => {
+                        let end = key + rng.gen_range(0..=key_space / 10);
+                        let removed = model.range(key..=end).map(|(k, c)| (*k, *c)).collect::<Vec<_>>();
+                        for (k, _) in &removed { model.remove(k); }
+                        let expected = removed.iter().map(|(_, c)| c).sum::<usize>();
+                        assert_eq!(graph.remove_range(key..=end), expected, "remove_range {}..={} {}", key, end, context);
+                        assert_matches_model(&graph, &model, &context);
+                    },
+                    15 => {
+                        let mut other = graph.split_off(&key);
+                        let upper = model.split_off(&key);
+                        assert_matches_model(&graph, &model, &format!("split_off lower {}", context));
+                        assert_matches_model(&other, &upper, &format!("split_off upper {}", context));
+                        if rng.gen_bool(0.5) {
+                            graph.merge(&other);
+                        } else {
+                            other.merge(&graph);
+                            graph = other;
+                        }
+                        model.extend(upper);
+                        assert_matches_model(&graph, &model, &format!("merge {}", context));
+                    },
+                    16 => {
+                        let data = model.iter()
+                            .flat_map(|(k, c)| std::iter::repeat(*k).take(*c))
+                            .collect::<Vec<_>>();
+                        graph = $graph::<i32, ORDER>::new_bulk("test", &data);
+                        assert_matches_model(&graph, &model, &format!("new_bulk {}", context));
+                    },
+                    _ => {
+                        let keys = graph.iter().rev().map(|e| e.borrow().key).collect::<Vec<_>>();
+                        assert_eq!(keys, model.keys().rev().cloned().collect::<Vec<_>>());
+                    }
+                }
+
+                if step % 100 == 0 || step == steps - 1 { assert_matches_model(&graph, &model, &context); }
+            }
+        }
+
+        #[test]
+        fn differential_3_degree() {
+            for seed in 0..20 { differential::<3>(seed, 2_000, 200); }
+            differential::<3>(20, 10_000, 5_000);
+        }
+
+        #[test]
+        fn differential_4_degree() {
+            for seed in 0..20 { differential::<4>(seed, 2_000, 200); }
+            differential::<4>(20, 10_000, 5_000);
+        }
+
+        #[test]
+        fn differential_25_degree() {
+            for seed in 0..10 { differential::<25>(seed, 5_000, 1_000); }
+            differential::<25>(10, 20_000, 20_000);
+        }
+    };
+}
+
+macro_rules! graph_tests {
+    ($graph:ident) => {
+        #[test]
+        fn remove_range() {
+            let mut graph = $graph::<i32, 3>::new("test");
+            for i in 1..=100 { graph.insert(&i); }
+            for i in 10..=14 { graph.insert(&i); }
+
+            assert_eq!(graph.remove_range(10..=19), 15);
+            assert_eq!(graph.remove_range(10..20), 0);
+            for i in 10..=19 { assert!(graph.search(&i).is_none()); }
+            assert_eq!(graph.successor(&9).unwrap().borrow().key, 20);
+
+            assert_eq!(graph.remove_range(..5), 4);
+            assert_eq!(graph.remove_range(90..), 11);
+            assert_eq!(graph.key_min.unwrap(), 5);
+            assert_eq!(graph.element_min.as_ref().unwrap().borrow().key, 5);
+            assert_eq!(graph.key_max.unwrap(), 89);
+            assert_eq!(graph.element_max.as_ref().unwrap().borrow().key, 89);
+            assert_eq!(graph.count_elements_unique(), 75);
+
+            let keys: Vec<i32> = graph.into_iter().map(|e| e.borrow().key).collect();
+            let expected: Vec<i32> = (5..=9).chain(20..=89).collect();
+            assert_eq!(keys, expected);
+            for i in &expected { assert!(graph.search(i).is_some()); }
+
+            assert_eq!(graph.remove_range(..), 75);
+            assert_eq!(graph.count_elements_unique(), 0);
+            assert!(graph.key_min.is_none() && graph.element_max.is_none());
+            graph.insert(&1);
+            assert_eq!(graph.count_elements_agg(), 1);
+        }
+
+        #[test]
+        fn range() {
+            let mut graph = $graph::<i32, 3>::new("test");
+            for i in (0..100).step_by(2) { graph.insert(&i); }
+
+            let keys: Vec<i32> = graph.range(10..20).map(|e| e.borrow().key).collect();
+            assert_eq!(keys, vec![10, 12, 14, 16, 18]);
+            let keys: Vec<i32> = graph.range(9..=21).map(|e| e.borrow().key).collect();
+            assert_eq!(keys, vec![10, 12, 14, 16, 18, 20]);
+            let keys: Vec<i32> = graph.range((Excluded(10), Excluded(16))).map(|e| e.borrow().key).collect();
+            assert_eq!(keys, vec![12, 14]);
+            let keys: Vec<i32> = graph.range(..5).map(|e| e.borrow().key).collect();
+            assert_eq!(keys, vec![0, 2, 4]);
+            let keys: Vec<i32> = graph.range(95..).map(|e| e.borrow().key).collect();
+            assert_eq!(keys, vec![96, 98]);
+            assert_eq!(graph.range(..).count(), 50);
+            assert_eq!(graph.range(11..12).count(), 0);
+            assert_eq!(graph.range(100..200).count(), 0);
+            assert_eq!(graph.range(-10..0).count(), 0);
+
+            let empty = $graph::<i32, 3>::new("test");
+            assert_eq!(empty.range(..).count(), 0);
+            assert_eq!(empty.range(1..5).count(), 0);
+        }
+
+        #[test]
+        fn iter_rev() {
+            let mut graph = $graph::<i32, 3>::new("test");
+            assert_eq!(graph.iter().rev().count(), 0);
+            for i in (1..=50).rev() { graph.insert(&i); }
+
+            let keys: Vec<i32> = graph.iter().rev().map(|e| e.borrow().key).collect();
+            assert_eq!(keys, (1..=50).rev().collect::<Vec<i32>>());
+            let keys: Vec<i32> = graph.range(10..15).rev().map(|e| e.borrow().key).collect();
+            assert_eq!(keys, vec![14, 13, 12, 11, 10]);
+
+            let mut iter = graph.iter();
+            let mut keys = vec![];
+            loop {
+                match iter.next() { Some(e) => keys.push(e.borrow().key), None => break }
+                match iter.next_back() { Some(e) => keys.push(e.borrow().key), None => break }
+            }
+            assert_eq!(keys.len(), 50);
+            assert_eq!(&keys[..4], &[1, 50, 2, 49]);
+            assert_eq!(&keys[48..], &[25, 26]);
+            assert!(iter.next().is_none() && iter.next_back().is_none());
+        }
+
+        #[test]
+        fn floor_ceiling() {
+            let mut graph = $graph::<i32, 3>::new("test");
+            assert!(graph.floor(&1).is_none() && graph.ceiling(&1).is_none());
+            for i in (0..100).step_by(10) { graph.insert(&i); }
+
+            assert_eq!(graph.floor(&35).map(|e| e.borrow().key), Some(30));
+            assert_eq!(graph.floor(&30).map(|e| e.borrow().key), Some(30));
+            assert_eq!(graph.floor(&-1).map(|e| e.borrow().key), None);
+            assert_eq!(graph.floor(&1000).map(|e| e.borrow().key), Some(90));
+            assert_eq!(graph.ceiling(&35).map(|e| e.borrow().key), Some(40));
+            assert_eq!(graph.ceiling(&40).map(|e| e.borrow().key), Some(40));
+            assert_eq!(graph.ceiling(&-1).map(|e| e.borrow().key), Some(0));
+            assert_eq!(graph.ceiling(&91).map(|e| e.borrow().key), None);
+            assert_eq!(graph.predecessor(&30).map(|e| e.borrow().key), Some(20));
+            assert_eq!(graph.predecessor(&31).map(|e| e.borrow().key), Some(30));
+            assert_eq!(graph.predecessor(&0).map(|e| e.borrow().key), None);
+            assert_eq!(graph.successor(&30).map(|e| e.borrow().key), Some(40));
+            assert_eq!(graph.successor(&29).map(|e| e.borrow().key), Some(30));
+            assert_eq!(graph.successor(&90).map(|e| e.borrow().key), None);
+        }
+
+        #[test]
+        fn nearest_within() {
+            let mut graph = $graph::<i32, 3>::new("test");
+            assert!(graph.nearest(&1, 3).is_empty() && graph.within(&1, 5.0).is_empty());
+            for i in (0..100).step_by(10) { graph.insert(&i); }
+
+            let keys: Vec<i32> = graph.nearest(&42, 3).iter().map(|e| e.borrow().key).collect();
+            assert_eq!(keys, vec![40, 50, 30]);
+            let keys: Vec<i32> = graph.nearest(&50, 4).iter().map(|e| e.borrow().key).collect();
+            assert_eq!(keys, vec![50, 40, 60, 30]);
+            let keys: Vec<i32> = graph.nearest(&-5, 2).iter().map(|e| e.borrow().key).collect();
+            assert_eq!(keys, vec![0, 10]);
+            assert_eq!(graph.nearest(&42, 100).len(), 10);
+            assert!(graph.nearest(&42, 0).is_empty());
+
+            let keys: Vec<i32> = graph.within(&42, 12.0).iter().map(|e| e.borrow().key).collect();
+            assert_eq!(keys, vec![40, 50, 30]);
+            let keys: Vec<i32> = graph.within(&95, 5.0).iter().map(|e| e.borrow().key).collect();
+            assert_eq!(keys, vec![90]);
+            assert!(graph.within(&45, 4.0).is_empty());
+            assert_eq!(graph.within(&45, 100.0).len(), 10);
+        }
+
+        #[test]
+        fn rank_select() {
+            let mut graph = $graph::<i32, 3>::new("test");
+            assert_eq!(graph.rank(&5), 0);
+            assert!(graph.select(0).is_none());
+            for i in (0..100).step_by(2).rev() { graph.insert(&i); }
+            graph.insert(&10);
+            graph.insert(&10);
+
+            assert_eq!(graph.count_elements_unique(), 50);
+            assert_eq!(graph.count_elements_agg(), 52);
+            assert_eq!(graph.rank(&0), 0);
+            assert_eq!(graph.rank(&10), 5);
+            assert_eq!(graph.rank(&11), 6);
+            assert_eq!(graph.rank(&1000), 50);
+            for i in 0..50 {
+                assert_eq!(graph.select(i).unwrap().borrow().key, 2 * i as i32);
+            }
+            assert!(graph.select(50).is_none());
+
+            assert_eq!(graph.count_in_range(10..20), 5);
+            assert_eq!(graph.count_in_range(10..=20), 6);
+            assert_eq!(graph.count_in_range((Excluded(10), Excluded(20))), 4);
+            assert_eq!(graph.count_in_range(..), 50);
+            assert_eq!(graph.count_in_range(..7), 4);
+            assert_eq!(graph.count_in_range(95..), 2);
+            assert_eq!(graph.count_in_range((Excluded(20), Excluded(10))), 0);
+        }
+
+        fn new_bulk_sizes<const ORDER: usize>()
+        where [(); ORDER + 1]: {
+            for base in [ORDER, 2 * ORDER, ORDER * ORDER, ORDER * ORDER * ORDER] {
+                for n in base.saturating_sub(2)..=(base + 2) {
+                    let data: Vec<i32> = (0..n as i32).collect();
+                    let graph = $graph::<i32, ORDER>::new_bulk("test", &data);
+                    assert_eq!(graph.validate(), Ok(()), "ORDER {} n {}", ORDER, n);
+                    assert_eq!(graph.count_elements_unique(), n);
+                    if n + 1 > 2 * (ORDER + 1) {
+                        let (leaf, _) = Node::extreme_position(&graph.root, false).unwrap();
+                        assert_eq!(leaf.borrow().size, ORDER, "ORDER {} n {}", ORDER, n);
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn new_bulk_node_sizes() {
+            new_bulk_sizes::<3>();
+            new_bulk_sizes::<4>();
+            new_bulk_sizes::<5>();
+            new_bulk_sizes::<25>();
+        }
+
+        #[test]
+        fn new_bulk_nan() {
+            let data = [1.0f64, f64::NAN, 0.5f64, f64::NAN, 2.0f64, 0.5f64];
+            let graph = $graph::<f64, 3>::new_bulk("test", &data);
+            assert_eq!(graph.validate(), Ok(()));
+            assert_eq!(graph.count_elements_unique(), 3);
+            assert_eq!(graph.count_elements_agg(), 4);
+            assert_eq!(graph.key_min, Some(0.5f64));
+            assert_eq!(graph.key_max, Some(2.0f64));
+        }
+
+        #[test]
+        fn new_bulk() {
+            let graph = $graph::<i32, 3>::new_bulk("test", &[]);
+            assert_eq!(graph.count_elements_unique(), 0);
+            assert!(graph.search(&1).is_none());
+
+            for n in [1, 2, 3, 4, 5, 15, 16, 17, 100, 1000] {
+                let data: Vec<i32> = (0..n).rev().chain((0..n).step_by(3)).collect();
+                let bulk = $graph::<i32, 3>::new_bulk("test", &data);
+
+                assert_eq!(bulk.count_elements_unique(), n as usize);
+                assert_eq!(bulk.count_elements_agg(), data.len());
+                assert_eq!(bulk.key_min, Some(0));
+                assert_eq!(bulk.key_max, Some(n - 1));
+                let items: Vec<(i32, usize)> = bulk.iter()
+                    .map(|e| (e.borrow().key, e.borrow().counter)).collect();
+                let expected: Vec<(i32, usize)> = (0..n)
+                    .map(|key| (key, if key % 3 == 0 { 2 } else { 1 })).collect();
+                assert_eq!(items, expected);
+                let keys: Vec<i32> = bulk.iter().rev().map(|e| e.borrow().key).collect();
+                assert_eq!(keys, (0..n).rev().collect::<Vec<i32>>());
+                for key in 0..n {
+                    assert_eq!(bulk.search(&key).unwrap().borrow().key, key);
+                    assert_eq!(bulk.rank(&key), key as usize);
+                }
+                assert!(bulk.search(&n).is_none());
+            }
+
+            let mut graph = $graph::<i32, 25>::new_bulk("test", &(0..5000).collect::<Vec<i32>>());
+            for i in 5000..5100 { graph.insert(&i); }
+            assert_eq!(graph.count_elements_unique(), 5100);
+            assert_eq!(graph.remove_range(100..4900), 4800);
+            assert_eq!(graph.count_in_range(..), 300);
+        }
+
+        #[test]
+        fn merge() {
+            let mut graph = $graph::<i32, 3>::new_bulk("test", &(0..50).collect::<Vec<i32>>());
+            let other = $graph::<i32, 3>::new_bulk("other", &(25..75).chain(30..35).collect::<Vec<i32>>());
+            graph.merge(&other);
+
+            assert_eq!(graph.count_elements_unique(), 75);
+            assert_eq!(graph.count_elements_agg(), 105);
+            assert_eq!(graph.key_min, Some(0));
+            assert_eq!(graph.key_max, Some(74));
+            for i in 0..75 {
+                let expected = match i { 30..=34 => 3, 25..=49 => 2, _ => 1 };
+                assert_eq!(graph.search(&i).unwrap().borrow().counter, expected);
+            }
+            let keys: Vec<i32> = graph.iter().rev().map(|e| e.borrow().key).collect();
+            assert_eq!(keys, (0..75).rev().collect::<Vec<i32>>());
+            assert_eq!(other.count_elements_agg(), 55);
+
+            let mut empty = $graph::<i32, 3>::new("test");
+            empty.merge(&graph);
+            assert_eq!(empty.count_elements_agg(), 105);
+            graph.merge(&$graph::new("empty"));
+            assert_eq!(graph.count_elements_agg(), 105);
+            graph.insert(&100);
+            assert_eq!(graph.key_max, Some(100));
+        }
+
+        #[test]
+        fn merge_shared_clone() {
+            let mut graph = $graph::<i32, 3>::new_bulk("test", &(0..30).collect::<Vec<i32>>());
+            graph.insert(&7);
+            let alias = graph.clone();
+            graph.merge(&alias);
+
+            assert_eq!(graph.validate(), Ok(()));
+            assert_eq!(graph.count_elements_unique(), 30);
+            assert_eq!(graph.count_elements_agg(), 62);
+            assert_eq!(graph.search(&7).unwrap().borrow().counter, 4);
+            assert_eq!(graph.search(&8).unwrap().borrow().counter, 2);
+        }
+
+        #[test]
+        fn set_operations() {
+            let graph = $graph::<i32, 3>::new_bulk("test", &[1, 2, 2, 3, 4, 4, 4, 5]);
+            let other = $graph::<i32, 3>::new_bulk("other", &[4, 5, 5, 5, 6, 7]);
+            let items = |graph: &$graph<i32, 3>| -> Vec<(i32, usize)> {
+                graph.iter().map(|e| (e.borrow().key, e.borrow().counter)).collect()
+            };
+
+            assert_eq!(items(&graph.intersection(&other, CounterSemantics::Min)), vec![(4, 1), (5, 1)]);
+            assert_eq!(items(&graph.intersection(&other, CounterSemantics::Max)), vec![(4, 3), (5, 3)]);
+            assert_eq!(items(&graph.intersection(&other, CounterSemantics::Sum)), vec![(4, 4), (5, 4)]);
+            assert_eq!(items(&graph.difference(&other)), vec![(1, 1), (2, 2), (3, 1), (4, 2)]);
+            assert_eq!(items(&other.difference(&graph)), vec![(5, 2), (6, 1), (7, 1)]);
+            assert_eq!(
+                items(&graph.symmetric_difference(&other)),
+                vec![(1, 1), (2, 2), (3, 1), (4, 2), (5, 2), (6, 1), (7, 1)]
+            );
+            assert_eq!(items(&graph.difference(&graph)), vec![]);
+
+            let intersection = graph.intersection(&other, CounterSemantics::Sum);
+            assert_eq!(intersection.key_min, Some(4));
+            assert_eq!(intersection.count_elements_agg(), 8);
+            assert!(intersection.search(&4).is_some() && intersection.search(&1).is_none());
+            assert_eq!(graph.count_elements_agg(), 8);
+            assert_eq!(other.count_elements_agg(), 6);
+
+            let empty = $graph::<i32, 3>::new("empty");
+            assert_eq!(graph.intersection(&empty, CounterSemantics::Min).count_elements_unique(), 0);
+            assert_eq!(graph.difference(&empty).count_elements_agg(), 8);
+
+            let mut graph = graph;
+            graph.insert_weighted(&4, 3.0f32);
+            let weighted = |graph: &$graph<i32, 3>, key: i32| graph.search(&key).unwrap().borrow().weighted_counter;
+            assert_eq!(graph.intersection(&other, CounterSemantics::Min).count_elements_weighted(), 2.0f32);
+            let intersection = graph.intersection(&other, CounterSemantics::Max);
+            assert_eq!((weighted(&intersection, 4), weighted(&intersection, 5)), (6.0f32, 3.0f32));
+            let intersection = graph.intersection(&other, CounterSemantics::Sum);
+            assert_eq!((weighted(&intersection, 4), weighted(&intersection, 5)), (7.0f32, 4.0f32));
+            let difference = graph.difference(&other);
+            assert_eq!(difference.search(&4).unwrap().borrow().counter, 3);
+            assert_eq!(weighted(&difference, 4), 4.5f32);
+            let symmetric_difference = graph.symmetric_difference(&other);
+            assert_eq!(weighted(&symmetric_difference, 4), 4.5f32);
+            assert_eq!(weighted(&symmetric_difference, 5), 2.0f32);
+        }
+
+        #[test]
+        fn split_off() {
+            let mut graph = $graph::<i32, 3>::new_bulk("test", &(0..100).collect::<Vec<i32>>());
+            graph.insert(&50);
+            let mut other = graph.split_off(&50);
+
+            assert_eq!(graph.count_elements_unique(), 50);
+            assert_eq!(other.count_elements_agg(), 51);
+            assert_eq!((graph.key_min, graph.key_max), (Some(0), Some(49)));
+            assert_eq!((other.key_min, other.key_max), (Some(50), Some(99)));
+            let keys: Vec<i32> = graph.iter().rev().map(|e| e.borrow().key).collect();
+            assert_eq!(keys, (0..50).rev().collect::<Vec<i32>>());
+            let keys: Vec<i32> = other.iter().map(|e| e.borrow().key).collect();
+            assert_eq!(keys, (50..100).collect::<Vec<i32>>());
+            assert!(graph.search(&50).is_none() && other.search(&49).is_none());
+
+            let rest = other.split_off(&1000);
+            assert_eq!(rest.count_elements_unique(), 0);
+            assert_eq!(other.count_elements_unique(), 50);
+            let all = other.split_off(&-5);
+            assert_eq!(other.count_elements_unique(), 0);
+            assert!(other.key_min.is_none() && other.element_max.is_none());
+            assert_eq!(all.count_elements_agg(), 51);
+
+            graph.insert(&200);
+            other.insert(&7);
+            assert_eq!(graph.key_max, Some(200));
+            assert_eq!(other.key_min, Some(7));
+        }
+
+        #[test]
+        fn insert_n_weighted() {
+            let mut graph = $graph::<i32, 3>::new("test");
+            for i in 0..20 { graph.insert(&i); }
+
+            assert_eq!(graph.insert_n(&5, 1000).unwrap().borrow().counter, 1001);
+            assert_eq!(graph.insert_n(&50, 300).unwrap().borrow().counter, 300);
+            assert!(graph.insert_n(&60, 0).is_none());
+            assert!(graph.search(&60).is_none());
+            assert_eq!(graph.insert_n(&5, 0).unwrap().borrow().counter, 1001);
+            assert_eq!(graph.count_elements_agg(), 1320);
+            assert_eq!(graph.count_elements_weighted(), 1320.0f32);
+            assert_eq!(graph.key_max, Some(50));
+
+            let element = graph.insert_weighted(&7, 0.25f32);
+            assert_eq!(element.borrow().counter, 2);
+            assert_eq!(element.borrow().weighted_counter, 1.25f32);
+            let element = graph.insert_weighted(&-3, 2.5f32);
+            assert_eq!(element.borrow().counter, 1);
+            assert_eq!(element.borrow().weighted_counter, 2.5f32);
+            assert_eq!(graph.count_elements_agg(), 1322);
+            assert_eq!(graph.count_elements_weighted(), 1322.75f32);
+            assert_eq!(graph.key_min, Some(-3));
+
+            let other = $graph::<i32, 3>::new_bulk("other", &[7, 8]);
+            let intersection = graph.intersection(&other, CounterSemantics::Sum);
+            assert_eq!(intersection.count_elements_weighted(), 4.25f32);
+            let intersection = graph.intersection(&other, CounterSemantics::Min);
+            assert_eq!(intersection.count_elements_weighted(), 2.0f32);
+            graph.merge(&other);
+            assert_eq!(graph.count_elements_weighted(), 1324.75f32);
+        }
+
+        #[test]
+        fn insert_even_order() {
+            let mut graph = $graph::<i32, 4>::new("test");
+            for i in (0..200).rev() { graph.insert(&i); }
+            for i in 0..200 { assert_eq!(graph.search(&i).unwrap().borrow().key, i); }
+            let keys = graph.into_iter().map(|element| element.borrow().key).collect::<Vec<_>>();
+            assert_eq!(keys, (0..200).collect::<Vec<_>>());
+            assert!(graph.search(&200).is_none());
+            assert_eq!(graph.key_min, Some(0));
+            assert_eq!(graph.key_max, Some(199));
+        }
+
+        #[test]
+        fn insert_duplicate_of_promoted_key() {
+            for n in 1..40 {
+                for key in 0..n {
+                    let mut graph = $graph::<i32, 3>::new("test");
+                    for i in 0..n { graph.insert(&i); }
+                    assert_eq!(graph.insert(&key).borrow().counter, 2);
+                    assert_eq!(graph.search(&key).unwrap().borrow().counter, 2);
+                }
+            }
+        }
+    };
+}